@@ -0,0 +1,78 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use hashbrown::HashSet;
+use online_codes::adjacency::{
+    sample_with_exclusive_repeats_into, DegreeDistribution, DegreeSampler,
+};
+use online_codes::rng::{AdjacencyRng, XoshiroRng};
+
+const EPSILONS: &[f64] = &[0.01, 0.1];
+const SAMPLES: u64 = 1000;
+
+fn bench_degree_sample(c: &mut Criterion) {
+    let mut group = c.benchmark_group("degree_sample");
+    group.throughput(Throughput::Elements(SAMPLES));
+    for &epsilon in EPSILONS {
+        for &sampler in &[DegreeSampler::InverseCdf, DegreeSampler::Alias] {
+            let degree_distribution = DegreeDistribution::new(epsilon).with_sampler(sampler);
+            group.bench_function(
+                BenchmarkId::from_parameter(format!("{:?}/{}", sampler, epsilon)),
+                |b| {
+                    let mut rng = XoshiroRng::new(0, 0);
+                    b.iter(|| {
+                        (0..SAMPLES)
+                            .map(|_| degree_distribution.sample(&mut rng))
+                            .sum::<usize>()
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+// The sampler before it switched to a sorted buffer, for comparison.
+fn hash_set_sample<R: AdjacencyRng>(rng: &mut R, high_exclusive: usize, num: usize) -> Vec<usize> {
+    let mut selected = HashSet::with_capacity(num);
+    for _ in 0..num {
+        let sample = rng.below(high_exclusive);
+        if !selected.insert(sample) {
+            selected.remove(&sample);
+        }
+    }
+    selected.into_iter().collect()
+}
+
+fn bench_adjacent_blocks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("adjacent_blocks");
+    group.throughput(Throughput::Elements(SAMPLES));
+    let num_blocks = 100_000;
+    let degree_distribution = DegreeDistribution::new(0.01);
+    group.bench_function("hash_set", |b| {
+        b.iter(|| {
+            (0..SAMPLES)
+                .map(|check_block_id| {
+                    let mut rng = XoshiroRng::new(0, check_block_id);
+                    let degree = degree_distribution.sample(&mut rng);
+                    hash_set_sample(&mut rng, num_blocks, degree).len()
+                })
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("sorted_buffer", |b| {
+        let mut adjacent = Vec::new();
+        b.iter(|| {
+            (0..SAMPLES)
+                .map(|check_block_id| {
+                    let mut rng = XoshiroRng::new(0, check_block_id);
+                    let degree = degree_distribution.sample(&mut rng);
+                    sample_with_exclusive_repeats_into(&mut rng, num_blocks, degree, &mut adjacent);
+                    adjacent.len()
+                })
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_degree_sample, bench_adjacent_blocks);
+criterion_main!(benches);
@@ -0,0 +1,195 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use online_codes::decode::{BlockOutcome, Decoder};
+use online_codes::encode::OnlineCoder;
+use online_codes::rng::RngAlgorithm;
+use online_codes::types::CodeParameters;
+use rand::{Rng, RngCore};
+use rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256StarStar;
+
+const BLOCK_COUNTS: &[usize] = &[10, 1_000, 100_000, 1_000_000];
+const BLOCK_SIZES: &[usize] = &[16, 1024, 65536];
+const LOSS_RATES: &[f64] = &[0.0, 0.1, 0.5];
+// Skip combinations which would need more than this much source data.
+const MAX_DATA_LEN: usize = 64 << 20;
+
+fn parameters() -> impl Iterator<Item = (usize, usize)> {
+    BLOCK_COUNTS
+        .iter()
+        .flat_map(|&num_blocks| BLOCK_SIZES.iter().map(move |&size| (num_blocks, size)))
+        .filter(|(num_blocks, block_size)| num_blocks * block_size <= MAX_DATA_LEN)
+}
+
+fn random_data(len: usize) -> Vec<u8> {
+    let mut data = vec![0; len];
+    Xoshiro256StarStar::seed_from_u64(0).fill_bytes(&mut data);
+    data
+}
+
+// Enough check blocks to decode `num_blocks` blocks, with the given fraction of them dropped.
+fn received_blocks(
+    num_blocks: usize,
+    block_size: usize,
+    loss: f64,
+    parameters: &CodeParameters,
+) -> Vec<(u64, Vec<u8>)> {
+    let coder = OnlineCoder::with_code_parameters(block_size, parameters);
+    let mut decoder = Decoder::with_code_parameters(num_blocks, block_size, 0, parameters, 0);
+    let mut loss_rng = Xoshiro256StarStar::seed_from_u64(1);
+    let mut received = Vec::new();
+    for (check_block_id, check_block) in coder.encode(random_data(num_blocks * block_size), 0) {
+        if loss_rng.gen::<f64>() < loss {
+            continue;
+        }
+        let done = decoder
+            .decode_block(check_block_id, &check_block)
+            .is_complete();
+        received.push((check_block_id, check_block));
+        if done {
+            return received;
+        }
+    }
+    unreachable!()
+}
+
+fn bench_outer_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("outer_encode");
+    group.sample_size(10);
+    for (num_blocks, block_size) in parameters() {
+        let data = random_data(num_blocks * block_size);
+        let coder = OnlineCoder::new(block_size);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{}x{}", num_blocks, block_size)),
+            &data,
+            |b, data| b.iter(|| coder.outer_encode(data, 0)),
+        );
+    }
+    group.finish();
+}
+
+fn bench_block_iter_next(c: &mut Criterion) {
+    let mut group = c.benchmark_group("block_iter_next");
+    for (num_blocks, block_size) in parameters() {
+        let mut block_iter =
+            OnlineCoder::new(block_size).encode(random_data(num_blocks * block_size), 0);
+        group.throughput(Throughput::Bytes(block_size as u64));
+        group.bench_function(
+            BenchmarkId::from_parameter(format!("{}x{}", num_blocks, block_size)),
+            |b| b.iter(|| block_iter.next()),
+        );
+    }
+    group.finish();
+}
+
+fn bench_decode_block(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_block");
+    group.sample_size(10);
+    for (num_blocks, block_size) in parameters() {
+        for &loss in LOSS_RATES {
+            let blocks = received_blocks(num_blocks, block_size, loss, &CodeParameters::default());
+            let decoder = Decoder::new(num_blocks, block_size, 0, 0);
+            group.throughput(Throughput::Elements(blocks.len() as u64));
+            group.bench_function(
+                BenchmarkId::from_parameter(format!("{}x{}/{}", num_blocks, block_size, loss)),
+                |b| {
+                    b.iter_batched(
+                        || decoder.clone(),
+                        |mut decoder| {
+                            for (check_block_id, check_block) in &blocks {
+                                decoder.decode_block(*check_block_id, check_block);
+                            }
+                            decoder
+                        },
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_adjacency_cache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("adjacency_cache");
+    group.sample_size(10);
+    let (num_blocks, block_size, loss) = (100_000, 16, 0.5);
+    for &rng_algorithm in &[RngAlgorithm::Xoshiro256StarStar, RngAlgorithm::ChaCha8] {
+        let parameters = CodeParameters {
+            rng_algorithm,
+            ..CodeParameters::default()
+        };
+        let blocks = received_blocks(num_blocks, block_size, loss, &parameters);
+        group.throughput(Throughput::Elements(blocks.len() as u64));
+        for &cache_adjacencies in &[false, true] {
+            let mut decoder =
+                Decoder::with_code_parameters(num_blocks, block_size, 0, &parameters, 0);
+            decoder.cache_adjacencies = cache_adjacencies;
+            let name = if cache_adjacencies {
+                "cached"
+            } else {
+                "regenerated"
+            };
+            group.bench_function(
+                BenchmarkId::from_parameter(format!("{:?}/{}", rng_algorithm, name)),
+                |b| {
+                    b.iter_batched(
+                        || decoder.clone(),
+                        |mut decoder| {
+                            for (check_block_id, check_block) in &blocks {
+                                decoder.decode_block(*check_block_id, check_block);
+                            }
+                            decoder
+                        },
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_round_trip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("round_trip");
+    group.sample_size(10);
+    for (num_blocks, block_size) in parameters() {
+        let data = random_data(num_blocks * block_size);
+        for &loss in LOSS_RATES {
+            group.throughput(Throughput::Bytes(data.len() as u64));
+            group.bench_function(
+                BenchmarkId::from_parameter(format!("{}x{}/{}", num_blocks, block_size, loss)),
+                |b| {
+                    b.iter(|| {
+                        let mut loss_rng = Xoshiro256StarStar::seed_from_u64(1);
+                        let mut decoder = Decoder::new(num_blocks, block_size, 0, 0);
+                        for (check_block_id, check_block) in
+                            OnlineCoder::new(block_size).encode(data.clone(), 0)
+                        {
+                            if loss_rng.gen::<f64>() < loss {
+                                continue;
+                            }
+                            if let BlockOutcome::Completed(decoded) =
+                                decoder.decode_block(check_block_id, &check_block)
+                            {
+                                return decoded;
+                            }
+                        }
+                        unreachable!()
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_outer_encode,
+    bench_block_iter_next,
+    bench_decode_block,
+    bench_adjacency_cache,
+    bench_round_trip
+);
+criterion_main!(benches);
@@ -0,0 +1,101 @@
+// Peak heap use of decoding objects of many small blocks, which criterion doesn't measure, along
+// with the time taken by a single run. Run with `cargo bench --bench decoder_memory`.
+
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+use rand::{Rng, RngCore};
+use rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256StarStar;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+const CASES: &[(usize, usize, f64)] = &[
+    (100_000, 16, 0.0),
+    (100_000, 16, 0.5),
+    (1_000_000, 16, 0.0),
+    (1_000_000, 16, 0.5),
+];
+
+struct PeakAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+fn grow(size: usize) {
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        grow(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // Both buffers are live while the contents are copied.
+        grow(new_size);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator;
+
+// Enough check blocks to decode `num_blocks` blocks, with the given fraction of them dropped.
+fn received_blocks(num_blocks: usize, block_size: usize, loss: f64) -> Vec<(u64, Vec<u8>)> {
+    let mut data = vec![0; num_blocks * block_size];
+    Xoshiro256StarStar::seed_from_u64(0).fill_bytes(&mut data);
+    let mut decoder = Decoder::new(num_blocks, block_size, 0, 0);
+    let mut loss_rng = Xoshiro256StarStar::seed_from_u64(1);
+    let mut received = Vec::new();
+    for (check_block_id, check_block) in OnlineCoder::new(block_size).encode(data, 0) {
+        if loss_rng.gen::<f64>() < loss {
+            continue;
+        }
+        let done = decoder
+            .decode_block(check_block_id, &check_block)
+            .is_complete();
+        received.push((check_block_id, check_block));
+        if done {
+            return received;
+        }
+    }
+    unreachable!()
+}
+
+fn main() {
+    for &(num_blocks, block_size, loss) in CASES {
+        let blocks = received_blocks(num_blocks, block_size, loss);
+        let before = CURRENT.load(Ordering::Relaxed);
+        PEAK.store(before, Ordering::Relaxed);
+        let start = Instant::now();
+        let mut decoder = Decoder::new(num_blocks, block_size, 0, 0);
+        for (check_block_id, check_block) in &blocks {
+            if decoder
+                .decode_block(*check_block_id, check_block)
+                .is_complete()
+            {
+                break;
+            }
+        }
+        let elapsed = start.elapsed();
+        let peak = PEAK.load(Ordering::Relaxed) - before;
+        println!(
+            "{}x{}/{}: {} check blocks in {:.2?}, peak heap {:.1} MB",
+            num_blocks,
+            block_size,
+            loss,
+            blocks.len(),
+            elapsed,
+            peak as f64 / 1e6
+        );
+    }
+}
@@ -0,0 +1,23 @@
+// Generates the C header for the `capi` feature.
+
+#[cfg(feature = "capi")]
+fn main() {
+    use std::{env, path::PathBuf};
+
+    println!("cargo:rerun-if-changed=src/capi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=ONLINE_CODES_HEADER");
+
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_file(PathBuf::from(&crate_dir).join("cbindgen.toml"))
+        .expect("invalid cbindgen.toml");
+    let bindings = cbindgen::generate_with_config(&crate_dir, config)
+        .expect("failed to generate the C header");
+    bindings.write_to_file(PathBuf::from(env::var("OUT_DIR").unwrap()).join("online_codes.h"));
+    if let Ok(path) = env::var("ONLINE_CODES_HEADER") {
+        bindings.write_to_file(path);
+    }
+}
+
+#[cfg(not(feature = "capi"))]
+fn main() {}
@@ -0,0 +1,80 @@
+// Runs seeded encode/decode trials over simulated erasure channels and prints how much overhead
+// each choice of epsilon and outer code (precode) needs.
+//
+// Usage: cargo run --release --example overhead [num_blocks] [block_size] [trials]
+
+use online_codes::precode::{LdpcPrecode, NoPrecode, Precode};
+use online_codes::sim::{ErasureChannel, Simulation};
+use std::env;
+use std::sync::Arc;
+
+// Epsilon, a name for the precode, q, and the precode if it isn't the random one.
+type PrecodeConfig = (f64, &'static str, usize, Option<Arc<dyn Precode>>);
+
+fn main() {
+    let args: Vec<usize> = env::args()
+        .skip(1)
+        .map(|arg| arg.parse().expect("arguments must be integers"))
+        .collect();
+    let num_blocks = args.first().copied().unwrap_or(1000);
+    let block_size = args.get(1).copied().unwrap_or(64);
+    let trials = args.get(2).copied().unwrap_or(100);
+
+    let channels = vec![
+        ("bernoulli 10%", ErasureChannel::Bernoulli { loss: 0.1 }),
+        ("bernoulli 50%", ErasureChannel::Bernoulli { loss: 0.5 }),
+        (
+            "gilbert-elliott",
+            ErasureChannel::GilbertElliott {
+                good_to_bad: 0.01,
+                bad_to_good: 0.1,
+                good_loss: 0.01,
+                bad_loss: 0.8,
+            },
+        ),
+        (
+            "pattern 1 in 4",
+            ErasureChannel::Pattern(vec![false, false, false, true]),
+        ),
+    ];
+
+    println!(
+        "{:<16} {:>7} {:>8} {:>9} {:>9} {:>9} {:>10} {:>12}",
+        "channel", "epsilon", "precode", "mean", "p50", "p99", "P(fail@5%)", "decode time"
+    );
+    for (name, channel) in channels {
+        let precodes: Vec<PrecodeConfig> = vec![
+            (0.01, "q=3", 3, None),
+            (0.05, "q=3", 3, None),
+            (0.1, "q=3", 3, None),
+            (0.01, "q=5", 5, None),
+            (0.01, "none", 0, Some(Arc::new(NoPrecode))),
+            (0.01, "ldpc", 0, Some(Arc::new(LdpcPrecode))),
+        ];
+        for (epsilon, precode_name, q, precode) in precodes {
+            let report = Simulation {
+                epsilon,
+                q,
+                precode,
+                trials,
+                ..Simulation::new(num_blocks, block_size, channel.clone())
+            }
+            .run();
+            let show = |overhead: Option<f64>| match overhead {
+                Some(overhead) => format!("{:.3}", overhead),
+                None => "-".to_owned(),
+            };
+            println!(
+                "{:<16} {:>7} {:>8} {:>9} {:>9} {:>9} {:>10.3} {:>12?}",
+                name,
+                epsilon,
+                precode_name,
+                show(report.mean_overhead()),
+                show(report.overhead_quantile(0.5)),
+                show(report.overhead_quantile(0.99)),
+                report.failure_probability(0.05),
+                report.mean_decode_time(),
+            );
+        }
+    }
+}
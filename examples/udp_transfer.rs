@@ -0,0 +1,59 @@
+// Sends a file over UDP as a stream of check blocks, or receives one.
+//
+// Usage:
+//   cargo run --example udp_transfer send <file> <address> [block_size] [packets_per_second]
+//   cargo run --example udp_transfer receive <address> <file> [timeout_secs] [max_len]
+//
+// The sender runs until interrupted. Multicast addresses are joined by the receiver.
+
+use online_codes::udp::{UdpReceiver, UdpSender};
+use std::env;
+use std::net::SocketAddr;
+use std::process;
+use std::time::Duration;
+
+// Largest file the receiver accepts by default, as it allocates room for the whole file up front.
+const DEFAULT_MAX_LEN: u64 = 64 << 20;
+
+fn usage() -> ! {
+    eprintln!("usage: udp_transfer send <file> <address> [block_size] [packets_per_second]");
+    eprintln!("       udp_transfer receive <address> <file> [timeout_secs] [max_len]");
+    process::exit(2);
+}
+
+fn parse<T: std::str::FromStr>(arg: Option<&String>, default: T) -> T {
+    arg.map_or(Some(default), |arg| arg.parse().ok())
+        .unwrap_or_else(|| usage())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("send") if args.len() >= 3 => {
+            let target: SocketAddr = parse(args.get(2), "0.0.0.0:0".parse().unwrap());
+            let block_size = parse(args.get(3), 1024);
+            let rate = parse(args.get(4), 1000.0);
+            let mut sender = UdpSender::from_file(&args[1], block_size, 0, target, rate)
+                .expect("failed to read file");
+            sender.send_until(|| false).expect("failed to send");
+        }
+        Some("receive") if args.len() >= 3 => {
+            let address: SocketAddr = parse(args.get(1), "0.0.0.0:0".parse().unwrap());
+            // A timeout of 0 waits forever.
+            let timeout = match parse(args.get(3), 0) {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            };
+            let max_len = parse(args.get(4), DEFAULT_MAX_LEN);
+            let mut receiver = UdpReceiver::bind(address, 0, max_len).expect("failed to bind");
+            receiver
+                .receive_to_file(&args[2], timeout)
+                .expect("transfer failed");
+            println!(
+                "received {} packets, {} ignored",
+                receiver.packets_received, receiver.packets_ignored
+            );
+        }
+        _ => usage(),
+    }
+}
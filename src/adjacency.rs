@@ -0,0 +1,388 @@
+//! Generation of check block and auxiliary block adjacencies.
+//!
+//! Everything here (and the generators in `rng`) decides which blocks a
+//! `(stream_id, check_block_id)` is made of, so it is part of the wire format. It only relies on
+//! integer and basic IEEE 754 float arithmetic, never on an external crate's sampling code, so
+//! that dependency bumps can't change it. Any change to its output breaks decoding of blocks from
+//! existing senders, and is caught by `tests/golden.rs`.
+
+use crate::rng::{AdjacencyRng, RngAlgorithm, XoshiroRng};
+use crate::types::{BlockIndex, CheckBlockId, ObjectId, StreamId};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// How check block degrees are drawn from a `DegreeDistribution`. Both give the same
+/// distribution, but different degrees for the same check block, so the encoder and decoder must
+/// agree on it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DegreeSampler {
+    // Binary search over the CDF; the original choice.
+    #[default]
+    InverseCdf,
+    // Walker's alias method: O(1) per check block.
+    Alias,
+}
+
+/// The check block degree distribution from section 3.2 of the Maymounkov-Mazières paper.
+///
+/// Its tables are shared, so clones are cheap: build it once per epsilon and clone it into
+/// every encoder and decoder.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DegreeDistribution {
+    // `thresholds[i]` is P(degree <= i + 1) scaled to 2^64.
+    thresholds: Arc<[u64]>,
+    // Set by `for_blocks` when the distribution had to be cut down to fit the object. Check
+    // blocks of such objects cover distinct blocks.
+    truncated: bool,
+    // Built for `DegreeSampler::Alias`.
+    alias_table: Option<Arc<AliasTable>>,
+}
+
+impl DegreeDistribution {
+    pub fn new(epsilon: f64) -> DegreeDistribution {
+        let f = Self::paper_max_degree(epsilon);
+        let p1 = 1.0 - ((1.0 + 1.0 / f as f64) / (1.0 + epsilon));
+        // p_i = c / (i * (i - 1)) for i >= 2, which telescopes to the closed form CDF below.
+        let c = (1.0 - p1) * f as f64 / (f - 1) as f64;
+        let thresholds = (1..f)
+            .map(|k| to_threshold(p1 + c * (1.0 - 1.0 / k as f64)))
+            .collect();
+        DegreeDistribution {
+            thresholds,
+            truncated: false,
+            alias_table: None,
+        }
+    }
+
+    /// The distribution for an object of `num_augmented_blocks` blocks, see `adapted_to`.
+    pub fn for_blocks(epsilon: f64, num_augmented_blocks: usize) -> DegreeDistribution {
+        Self::new(epsilon).adapted_to(num_augmented_blocks)
+    }
+
+    /// This (paper) distribution as used for an object of `num_augmented_blocks` blocks. It is
+    /// unchanged, unless its maximum degree F is larger than the object. Then it is cut off at
+    /// the object's size, and, as in Luby's robust soliton distribution, low degrees get extra
+    /// weight `R / (i * n)` for `i < n / R`, with `R = sqrt(n)`, so that there is usually a block
+    /// ready to decode. The extra weight fades out as `n` approaches F.
+    pub fn adapted_to(&self, num_augmented_blocks: usize) -> DegreeDistribution {
+        assert!(!self.truncated, "the distribution has already been adapted");
+        let f = self.max_degree();
+        if f <= num_augmented_blocks {
+            return self.clone();
+        }
+        let n = num_augmented_blocks.max(1);
+        let mut weights = vec![0.0; n + 1];
+        let mut previous = 0.0;
+        for (degree, &threshold) in self.thresholds[..n].iter().enumerate() {
+            weights[degree + 1] = from_threshold(threshold) - previous;
+            previous = from_threshold(threshold);
+        }
+        let r = n.isqrt();
+        let fade = 1.0 - n as f64 / f as f64;
+        for (degree, weight) in weights.iter_mut().enumerate().take(n / r).skip(1) {
+            *weight += fade * r as f64 / (degree * n) as f64;
+        }
+        let total: f64 = weights.iter().sum();
+        let mut cumulative = 0.0;
+        let thresholds = weights[1..n]
+            .iter()
+            .map(|weight| {
+                cumulative += weight;
+                to_threshold(cumulative / total)
+            })
+            .collect();
+        DegreeDistribution {
+            thresholds,
+            truncated: true,
+            alias_table: None,
+        }
+        .with_sampler(self.sampler())
+    }
+
+    pub fn with_sampler(mut self, sampler: DegreeSampler) -> DegreeDistribution {
+        self.alias_table = match sampler {
+            DegreeSampler::InverseCdf => None,
+            DegreeSampler::Alias => match self.alias_table {
+                Some(alias_table) => Some(alias_table),
+                None => Some(Arc::new(AliasTable::new(&self.thresholds))),
+            },
+        };
+        self
+    }
+
+    pub fn sampler(&self) -> DegreeSampler {
+        match self.alias_table {
+            Some(_) => DegreeSampler::Alias,
+            None => DegreeSampler::InverseCdf,
+        }
+    }
+
+    // F is the smallest integer with (1 - epsilon/2)^F <= epsilon^2/4. Finding it by repeated
+    // multiplication rather than with `ln` keeps it exact on every platform.
+    fn paper_max_degree(epsilon: f64) -> usize {
+        let target = epsilon * epsilon / 4.0;
+        let mut f = 0;
+        let mut power = 1.0;
+        while power > target {
+            power *= 1.0 - epsilon / 2.0;
+            f += 1;
+        }
+        assert!(f > 1, "serious probability calculation error");
+        f
+    }
+
+    pub fn max_degree(&self) -> usize {
+        self.thresholds.len() + 1
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub fn sample<R: AdjacencyRng>(&self, rng: &mut R) -> usize {
+        match &self.alias_table {
+            Some(alias_table) => alias_table.sample(rng),
+            None => {
+                let r = rng.next_u64();
+                1 + self.thresholds.partition_point(|&threshold| threshold <= r)
+            }
+        }
+    }
+
+    /// Draws a degree, then that many blocks out of `num_blocks`, in increasing order.
+    pub fn sample_adjacent<R: AdjacencyRng>(&self, rng: &mut R, num_blocks: usize) -> Vec<usize> {
+        let mut adjacent = Vec::new();
+        self.sample_adjacent_into(rng, num_blocks, &mut adjacent);
+        adjacent
+    }
+
+    /// `sample_adjacent`, writing into `out` so that its buffer can be reused.
+    pub fn sample_adjacent_into<R: AdjacencyRng>(
+        &self,
+        rng: &mut R,
+        num_blocks: usize,
+        out: &mut Vec<usize>,
+    ) {
+        if num_blocks == 0 {
+            // The check blocks of an empty object are empty sums.
+            out.clear();
+            return;
+        }
+        let degree = self.sample(rng);
+        if self.truncated {
+            sample_distinct_into(rng, num_blocks, degree, out)
+        } else {
+            sample_with_exclusive_repeats_into(rng, num_blocks, degree, out)
+        }
+    }
+}
+
+const SCALE: f64 = 18_446_744_073_709_551_616.0; // 2^64
+
+/// Walker's alias method (in Vose's formulation), built from the integer thresholds with integer
+/// arithmetic only, so that every platform gets the same table. A draw `r` picks column
+/// `floor(r * n / 2^64)`, which yields its own degree if the rest of `r * n`, out of 2^64, is
+/// below the column's cutoff, and its alias otherwise.
+#[derive(Debug, PartialEq)]
+struct AliasTable {
+    cutoffs: Vec<u128>,
+    // Degree - 1.
+    aliases: Vec<usize>,
+}
+
+impl AliasTable {
+    const ONE: u128 = 1 << 64;
+
+    fn new(thresholds: &[u64]) -> AliasTable {
+        let n = thresholds.len() + 1;
+        let mut previous = 0;
+        let mut scaled: Vec<u128> = thresholds
+            .iter()
+            .map(|&threshold| {
+                let weight = u128::from(threshold.saturating_sub(previous));
+                previous = previous.max(threshold);
+                weight * n as u128
+            })
+            .collect();
+        scaled.push((Self::ONE - u128::from(previous)) * n as u128);
+
+        let mut cutoffs = vec![Self::ONE; n];
+        let mut aliases: Vec<usize> = (0..n).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&i| scaled[i] < Self::ONE);
+        while let (Some(&l), Some(&g)) = (small.last(), large.last()) {
+            small.pop();
+            large.pop();
+            cutoffs[l] = scaled[l];
+            aliases[l] = g;
+            // The weights sum to exactly n, so this never goes negative.
+            scaled[g] = scaled[g] + scaled[l] - Self::ONE;
+            if scaled[g] < Self::ONE {
+                small.push(g);
+            } else {
+                large.push(g);
+            }
+        }
+        AliasTable { cutoffs, aliases }
+    }
+
+    fn sample<R: AdjacencyRng>(&self, rng: &mut R) -> usize {
+        let m = u128::from(rng.next_u64()) * self.cutoffs.len() as u128;
+        let column = (m >> 64) as usize;
+        if u128::from(m as u64) < self.cutoffs[column] {
+            column + 1
+        } else {
+            self.aliases[column] + 1
+        }
+    }
+}
+
+fn to_threshold(probability: f64) -> u64 {
+    (probability * SCALE) as u64
+}
+
+fn from_threshold(threshold: u64) -> f64 {
+    threshold as f64 / SCALE
+}
+
+pub fn get_adjacent_blocks(
+    check_block_id: CheckBlockId,
+    stream_id: StreamId,
+    degree_distribution: &DegreeDistribution,
+    num_blocks: usize,
+) -> Vec<BlockIndex> {
+    let mut rng = XoshiroRng::new(stream_id, check_block_id);
+    degree_distribution.sample_adjacent(&mut rng, num_blocks)
+}
+
+pub fn get_adjacent_blocks_with_rng(
+    rng_algorithm: RngAlgorithm,
+    check_block_id: CheckBlockId,
+    stream_id: StreamId,
+    degree_distribution: &DegreeDistribution,
+    num_blocks: usize,
+) -> Vec<BlockIndex> {
+    let mut adjacent = Vec::new();
+    get_adjacent_blocks_with_rng_into(
+        rng_algorithm,
+        check_block_id,
+        stream_id,
+        degree_distribution,
+        num_blocks,
+        &mut adjacent,
+    );
+    adjacent
+}
+
+pub fn get_adjacent_blocks_with_rng_into(
+    rng_algorithm: RngAlgorithm,
+    check_block_id: CheckBlockId,
+    stream_id: StreamId,
+    degree_distribution: &DegreeDistribution,
+    num_blocks: usize,
+    out: &mut Vec<BlockIndex>,
+) {
+    let mut rng = rng_algorithm.rng(stream_id, check_block_id);
+    degree_distribution.sample_adjacent_into(&mut rng, num_blocks, out)
+}
+
+pub fn sample_with_exclusive_repeats<R: AdjacencyRng>(
+    rng: &mut R,
+    high_exclusive: usize,
+    num: usize,
+) -> Vec<usize> {
+    let mut selected = Vec::new();
+    sample_with_exclusive_repeats_into(rng, high_exclusive, num, &mut selected);
+    selected
+}
+
+/// `num` samples from `0..high_exclusive`, where a value drawn twice is dropped again, so the
+/// values drawn an odd number of times. They are written to `out` in increasing order.
+pub fn sample_with_exclusive_repeats_into<R: AdjacencyRng>(
+    rng: &mut R,
+    high_exclusive: usize,
+    num: usize,
+    out: &mut Vec<usize>,
+) {
+    out.clear();
+    out.extend((0..num).map(|_| rng.below(high_exclusive)));
+    out.sort_unstable();
+    let mut kept = 0;
+    let mut start = 0;
+    while start < out.len() {
+        let value = out[start];
+        let end = start + out[start..].iter().take_while(|&&v| v == value).count();
+        if (end - start) % 2 == 1 {
+            out[kept] = value;
+            kept += 1;
+        }
+        start = end;
+    }
+    out.truncate(kept);
+}
+
+pub fn sample_distinct<R: AdjacencyRng>(
+    rng: &mut R,
+    high_exclusive: usize,
+    num: usize,
+) -> Vec<usize> {
+    let mut selected = Vec::new();
+    sample_distinct_into(rng, high_exclusive, num, &mut selected);
+    selected
+}
+
+/// `num` distinct samples from `0..high_exclusive`, using Floyd's algorithm: one draw per sample,
+/// however close `num` is to `high_exclusive`. They are written to `out` in increasing order.
+pub fn sample_distinct_into<R: AdjacencyRng>(
+    rng: &mut R,
+    high_exclusive: usize,
+    num: usize,
+    out: &mut Vec<usize>,
+) {
+    out.clear();
+    let num = num.min(high_exclusive);
+    for j in high_exclusive - num..high_exclusive {
+        let sample = rng.below(j + 1);
+        match out.binary_search(&sample) {
+            // Everything selected so far is below `j`.
+            Ok(_) => out.push(j),
+            Err(position) => out.insert(position, sample),
+        }
+    }
+}
+
+pub fn num_aux_blocks(num_blocks: usize, epsilon: f64, q: usize) -> usize {
+    // `f64::ceil` isn't available without `std`.
+    let exact = 0.55_f64 * q as f64 * epsilon * num_blocks as f64;
+    let truncated = exact as usize;
+    if (truncated as f64) < exact {
+        truncated + 1
+    } else {
+        truncated
+    }
+}
+
+/// The data blocks XORed into each aux block, in increasing order: every data block goes into
+/// `q` aux blocks chosen at random.
+pub fn get_aux_block_members(
+    rng_algorithm: RngAlgorithm,
+    object_id: ObjectId,
+    num_blocks: usize,
+    num_auxiliary_blocks: usize,
+    q: usize,
+) -> Vec<Vec<BlockIndex>> {
+    let mut members = vec![Vec::new(); num_auxiliary_blocks];
+    if num_auxiliary_blocks == 0 {
+        return members;
+    }
+    let mut rng = rng_algorithm.rng(object_id, 0);
+    let mut aux_indices = Vec::with_capacity(q);
+    for i in 0..num_blocks {
+        sample_with_exclusive_repeats_into(&mut rng, num_auxiliary_blocks, q, &mut aux_indices);
+        for &aux_index in &aux_indices {
+            members[aux_index].push(i);
+        }
+    }
+    members
+}
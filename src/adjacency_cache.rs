@@ -0,0 +1,92 @@
+use crate::types::BlockIndex;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// The blocks adjacent to each buffered check block, indexed by storage slot, so that they don't
+/// need regenerating once it is used. All lists share one buffer, which is compacted once most
+/// of it belongs to lists which have been removed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AdjacencyCache {
+    blocks: Vec<u32>,
+    // Start and length in `blocks` of each slot's list; empty for slots with nothing cached.
+    ranges: Vec<(u32, u32)>,
+    len: usize,
+}
+
+impl AdjacencyCache {
+    pub fn new() -> AdjacencyCache {
+        AdjacencyCache::default()
+    }
+
+    /// Replaces whatever was cached for `slot`.
+    pub fn insert(&mut self, slot: usize, adjacent_blocks: &[BlockIndex]) {
+        self.remove(slot);
+        if slot >= self.ranges.len() {
+            self.ranges.resize(slot + 1, (0, 0));
+        }
+        // Compacting is linear in the number of slots as well, so wait for that much garbage.
+        let garbage = self.blocks.len() - self.len;
+        if garbage > usize::max(self.len, self.ranges.len()) {
+            self.compact();
+        }
+        let start = u32::try_from(self.blocks.len()).expect("adjacency cache is full");
+        self.blocks
+            .extend(adjacent_blocks.iter().map(|&block_index| {
+                u32::try_from(block_index).expect("block index too large to cache")
+            }));
+        self.ranges[slot] = (start, adjacent_blocks.len() as u32);
+        self.len += adjacent_blocks.len();
+    }
+
+    /// Copies the list cached for `slot` into `out`, returning false if there was none.
+    pub fn get(&self, slot: usize, out: &mut Vec<BlockIndex>) -> bool {
+        let (start, len) = match self.ranges.get(slot) {
+            Some(&range) if range.1 > 0 => range,
+            _ => return false,
+        };
+        out.clear();
+        out.extend(
+            self.blocks[start as usize..(start + len) as usize]
+                .iter()
+                .map(|&block_index| block_index as BlockIndex),
+        );
+        true
+    }
+
+    /// Moves the list cached for `slot` into `out`, returning false if there was none.
+    pub fn take(&mut self, slot: usize, out: &mut Vec<BlockIndex>) -> bool {
+        let found = self.get(slot, out);
+        self.remove(slot);
+        found
+    }
+
+    pub fn remove(&mut self, slot: usize) {
+        if let Some(range) = self.ranges.get_mut(slot) {
+            self.len -= range.1 as usize;
+            *range = (0, 0);
+        }
+    }
+
+    /// Number of cached block indices, each taking 4 bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn compact(&mut self) {
+        let mut blocks = Vec::with_capacity(2 * self.len);
+        for range in &mut self.ranges {
+            if range.1 > 0 {
+                let start = blocks.len() as u32;
+                blocks.extend_from_slice(
+                    &self.blocks[range.0 as usize..(range.0 + range.1) as usize],
+                );
+                range.0 = start;
+            }
+        }
+        self.blocks = blocks;
+    }
+}
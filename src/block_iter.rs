@@ -1,15 +1,21 @@
-use crate::types::{CheckBlockId, StreamId};
-use crate::util::{get_adjacent_blocks, xor_block};
-use rand::distributions::WeightedIndex;
+use crate::adjacency::{get_adjacent_blocks_with_rng_into, DegreeDistribution};
+use crate::rng::RngAlgorithm;
+use crate::types::{BlockIndex, CheckBlockId, StreamId};
+use crate::util::xor_block;
+use alloc::vec;
+use alloc::vec::Vec;
 
 #[derive(Clone, Debug)]
 pub struct BlockIter {
     pub data: Vec<u8>,
     pub aux_data: Vec<u8>,
     pub block_size: usize,
-    pub degree_distribution: WeightedIndex<f64>,
+    pub degree_distribution: DegreeDistribution,
+    pub rng_algorithm: RngAlgorithm,
     pub check_block_id: CheckBlockId,
     pub stream_id: StreamId,
+    // Reused for every check block.
+    pub adjacent_blocks: Vec<BlockIndex>,
 }
 
 impl Iterator for BlockIter {
@@ -18,13 +24,15 @@ impl Iterator for BlockIter {
         let num_blocks = self.data.len() / self.block_size;
         let num_aux_blocks = self.aux_data.len() / self.block_size;
         let mut check_block = vec![0; self.block_size];
-        let adjacent_blocks = get_adjacent_blocks(
+        get_adjacent_blocks_with_rng_into(
+            self.rng_algorithm,
             self.check_block_id,
             self.stream_id,
             &self.degree_distribution,
             num_blocks + num_aux_blocks,
+            &mut self.adjacent_blocks,
         );
-        for block_index in adjacent_blocks {
+        for &block_index in &self.adjacent_blocks {
             if block_index < num_blocks {
                 xor_block(
                     &mut check_block,
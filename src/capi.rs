@@ -0,0 +1,242 @@
+//! C ABI, enabled by the `capi` feature. The build script generates a matching header,
+//! `online_codes.h`, in `OUT_DIR` (and at `$ONLINE_CODES_HEADER` if that is set).
+//!
+//! Encoders and decoders are opaque pointers which must be freed with their `_free` function.
+//! Functions report errors with an `OnlineCodesStatus`, and never unwind into C: a panic is
+//! reported as `Panicked`, or as a null pointer, after which the object should only be freed.
+
+use crate::decode::Decoder;
+use crate::types::{CheckBlockId, StreamId};
+use crate::{decode_block, new_decoder, new_encoder, next_block, Encoder};
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OnlineCodesStatus {
+    Ok = 0,
+    // The decoder needs more check blocks.
+    InProgress = 1,
+    NullPointer = -1,
+    InvalidArgument = -2,
+    BufferTooSmall = -3,
+    // A bug in this library; the object involved should only be freed.
+    Panicked = -4,
+}
+
+pub struct OnlineCodesEncoder {
+    encoder: Encoder,
+    block_size: usize,
+}
+
+pub struct OnlineCodesDecoder {
+    decoder: Decoder,
+    block_size: usize,
+    len: usize,
+    result: Option<Vec<u8>>,
+}
+
+// Runs `f`, returning `on_panic` if it panics, as unwinding into C is undefined behaviour.
+fn catch_panic<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(on_panic)
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len)
+    }
+}
+
+/// Creates an encoder for a copy of `data`, or returns null if `block_size` is zero.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, unless `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn online_codes_encoder_new(
+    data: *const u8,
+    len: usize,
+    block_size: usize,
+    stream_id: StreamId,
+) -> *mut OnlineCodesEncoder {
+    if block_size == 0 || (data.is_null() && len > 0) {
+        return ptr::null_mut();
+    }
+    catch_panic(ptr::null_mut(), || {
+        let encoder = new_encoder(bytes(data, len).to_vec(), block_size, stream_id);
+        Box::into_raw(Box::new(OnlineCodesEncoder {
+            encoder,
+            block_size,
+        }))
+    })
+}
+
+/// # Safety
+///
+/// `encoder` must be null or come from `online_codes_encoder_new`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn online_codes_encoder_free(encoder: *mut OnlineCodesEncoder) {
+    if !encoder.is_null() {
+        catch_panic((), || drop(Box::from_raw(encoder)));
+    }
+}
+
+/// Writes the next check block's id to `check_block_id`, and its `block_size` bytes to `buf`.
+///
+/// # Safety
+///
+/// `encoder` must come from `online_codes_encoder_new`, `check_block_id` must be writable, and
+/// `buf` must point to `buf_len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn online_codes_encoder_next_block(
+    encoder: *mut OnlineCodesEncoder,
+    check_block_id: *mut CheckBlockId,
+    buf: *mut u8,
+    buf_len: usize,
+) -> OnlineCodesStatus {
+    if encoder.is_null() || check_block_id.is_null() || buf.is_null() {
+        return OnlineCodesStatus::NullPointer;
+    }
+    let encoder = &mut *encoder;
+    if buf_len < encoder.block_size {
+        return OnlineCodesStatus::BufferTooSmall;
+    }
+    catch_panic(OnlineCodesStatus::Panicked, || {
+        match next_block(&mut encoder.encoder) {
+            Some((id, block)) => {
+                *check_block_id = id;
+                ptr::copy_nonoverlapping(block.as_ptr(), buf, block.len());
+                OnlineCodesStatus::Ok
+            }
+            // Check block ids have run out.
+            None => OnlineCodesStatus::InvalidArgument,
+        }
+    })
+}
+
+/// Creates a decoder for `len` bytes encoded with `online_codes_encoder_new`, or returns null if
+/// `block_size` is zero.
+#[no_mangle]
+pub extern "C" fn online_codes_decoder_new(
+    len: usize,
+    block_size: usize,
+    stream_id: StreamId,
+) -> *mut OnlineCodesDecoder {
+    if block_size == 0 {
+        return ptr::null_mut();
+    }
+    catch_panic(ptr::null_mut(), || {
+        Box::into_raw(Box::new(OnlineCodesDecoder {
+            decoder: new_decoder(len, block_size, stream_id),
+            block_size,
+            len,
+            result: None,
+        }))
+    })
+}
+
+/// # Safety
+///
+/// `decoder` must be null or come from `online_codes_decoder_new`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn online_codes_decoder_free(decoder: *mut OnlineCodesDecoder) {
+    if !decoder.is_null() {
+        catch_panic((), || drop(Box::from_raw(decoder)));
+    }
+}
+
+/// Feeds in a check block. Returns `Ok` once the data is decoded, and `InProgress` until then.
+///
+/// # Safety
+///
+/// `decoder` must come from `online_codes_decoder_new`, and `block` must point to `block_len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn online_codes_decoder_decode_block(
+    decoder: *mut OnlineCodesDecoder,
+    check_block_id: CheckBlockId,
+    block: *const u8,
+    block_len: usize,
+) -> OnlineCodesStatus {
+    if decoder.is_null() || block.is_null() {
+        return OnlineCodesStatus::NullPointer;
+    }
+    let decoder = &mut *decoder;
+    if block_len != decoder.block_size {
+        return OnlineCodesStatus::InvalidArgument;
+    }
+    catch_panic(OnlineCodesStatus::Panicked, || {
+        if decoder.result.is_none() {
+            let block = bytes(block, block_len).to_vec();
+            decoder.result = decode_block((check_block_id, block), &mut decoder.decoder).complete();
+        }
+        online_codes_decoder_status(decoder)
+    })
+}
+
+/// Returns `Ok` if the data is decoded, and `InProgress` otherwise. The number of data blocks
+/// decoded so far and in total are written to `blocks_decoded` and `num_blocks` if they aren't
+/// null.
+///
+/// # Safety
+///
+/// `decoder` must come from `online_codes_decoder_new`.
+#[no_mangle]
+pub unsafe extern "C" fn online_codes_decoder_progress(
+    decoder: *const OnlineCodesDecoder,
+    blocks_decoded: *mut usize,
+    num_blocks: *mut usize,
+) -> OnlineCodesStatus {
+    if decoder.is_null() {
+        return OnlineCodesStatus::NullPointer;
+    }
+    let decoder = &*decoder;
+    catch_panic(OnlineCodesStatus::Panicked, || {
+        if !blocks_decoded.is_null() {
+            *blocks_decoded =
+                decoder.decoder.num_blocks - decoder.decoder.num_undecoded_data_blocks;
+        }
+        if !num_blocks.is_null() {
+            *num_blocks = decoder.decoder.num_blocks;
+        }
+        online_codes_decoder_status(decoder)
+    })
+}
+
+fn online_codes_decoder_status(decoder: &OnlineCodesDecoder) -> OnlineCodesStatus {
+    if decoder.result.is_some() {
+        OnlineCodesStatus::Ok
+    } else {
+        OnlineCodesStatus::InProgress
+    }
+}
+
+/// Copies the decoded data, which is as long as the `len` the decoder was created with, to `buf`.
+///
+/// # Safety
+///
+/// `decoder` must come from `online_codes_decoder_new`, and `buf` must point to `buf_len`
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn online_codes_decoder_result(
+    decoder: *const OnlineCodesDecoder,
+    buf: *mut u8,
+    buf_len: usize,
+) -> OnlineCodesStatus {
+    if decoder.is_null() || (buf.is_null() && buf_len > 0) {
+        return OnlineCodesStatus::NullPointer;
+    }
+    let decoder = &*decoder;
+    catch_panic(OnlineCodesStatus::Panicked, || match &decoder.result {
+        Some(_) if buf_len < decoder.len => OnlineCodesStatus::BufferTooSmall,
+        Some(result) => {
+            if !result.is_empty() {
+                ptr::copy_nonoverlapping(result.as_ptr(), buf, result.len());
+            }
+            OnlineCodesStatus::Ok
+        }
+        None => OnlineCodesStatus::InProgress,
+    })
+}
@@ -0,0 +1,372 @@
+//! Broadcasting many objects at once over a one-way channel. A `Carousel` interleaves the check
+//! blocks of every active object, and periodically announces the objects so that receivers
+//! joining at any time learn how to decode them. A `CarouselReceiver` demultiplexes the packets
+//! by stream id into one decoder per object.
+
+use crate::decode::Decoder;
+use crate::types::{CheckBlockId, StreamId};
+use crate::{decode_block, new_decoder, new_encoder, next_block, Encoder};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use hashbrown::{HashMap, HashSet};
+
+/// Everything a receiver needs to start decoding an object.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObjectAnnouncement {
+    pub stream_id: StreamId,
+    pub len: u64,
+    pub block_size: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CarouselPacket {
+    Announcement(ObjectAnnouncement),
+    Block {
+        stream_id: StreamId,
+        check_block_id: CheckBlockId,
+        data: Vec<u8>,
+    },
+}
+
+const ANNOUNCEMENT_TAG: u8 = 0;
+const BLOCK_TAG: u8 = 1;
+
+fn read_u64(bytes: &[u8], start: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[start..start + 8]);
+    u64::from_be_bytes(buf)
+}
+
+impl CarouselPacket {
+    /// A tag byte followed by the fields, big-endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            CarouselPacket::Announcement(announcement) => {
+                bytes.push(ANNOUNCEMENT_TAG);
+                bytes.extend_from_slice(&announcement.stream_id.to_be_bytes());
+                bytes.extend_from_slice(&announcement.len.to_be_bytes());
+                bytes.extend_from_slice(&announcement.block_size.to_be_bytes());
+            }
+            CarouselPacket::Block {
+                stream_id,
+                check_block_id,
+                data,
+            } => {
+                bytes.push(BLOCK_TAG);
+                bytes.extend_from_slice(&stream_id.to_be_bytes());
+                bytes.extend_from_slice(&check_block_id.to_be_bytes());
+                bytes.extend_from_slice(data);
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<CarouselPacket> {
+        match bytes.first() {
+            Some(&ANNOUNCEMENT_TAG) if bytes.len() == 21 => {
+                let mut block_size = [0; 4];
+                block_size.copy_from_slice(&bytes[17..21]);
+                Some(CarouselPacket::Announcement(ObjectAnnouncement {
+                    stream_id: read_u64(bytes, 1),
+                    len: read_u64(bytes, 9),
+                    block_size: u32::from_be_bytes(block_size),
+                }))
+            }
+            Some(&BLOCK_TAG) if bytes.len() >= 17 => Some(CarouselPacket::Block {
+                stream_id: read_u64(bytes, 1),
+                check_block_id: read_u64(bytes, 9),
+                data: bytes[17..].to_vec(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+struct CarouselObject {
+    announcement: ObjectAnnouncement,
+    encoder: Encoder,
+    weight: u32,
+    priority: u32,
+    // Smooth weighted round robin state.
+    current_weight: i64,
+    blocks_sent: u64,
+}
+
+/// Schedules check blocks from several objects onto one channel.
+///
+/// Only objects with the highest priority present are sent; objects with lower priorities wait
+/// until those are retired. Within a priority, each object gets a share of the check blocks in
+/// proportion to its weight, spread out as evenly as possible. Every active object is announced
+/// when it is added, and then in turn every `announcement_interval` packets.
+pub struct Carousel {
+    objects: Vec<CarouselObject>,
+    announcement_interval: usize,
+    packets_since_announcement: usize,
+    next_announcement: usize,
+    pending_announcements: VecDeque<ObjectAnnouncement>,
+}
+
+impl Default for Carousel {
+    fn default() -> Carousel {
+        Carousel::new(100)
+    }
+}
+
+impl Carousel {
+    pub fn new(announcement_interval: usize) -> Carousel {
+        assert!(announcement_interval > 0);
+        Carousel {
+            objects: Vec::new(),
+            announcement_interval,
+            packets_since_announcement: 0,
+            next_announcement: 0,
+            pending_announcements: VecDeque::new(),
+        }
+    }
+
+    /// Adds an object, returning false if one with the same stream id is already active, or if
+    /// the block size or weight is zero.
+    pub fn add_object(
+        &mut self,
+        stream_id: StreamId,
+        data: Vec<u8>,
+        block_size: u32,
+        weight: u32,
+        priority: u32,
+    ) -> bool {
+        if weight == 0 || block_size == 0 || self.has_object(stream_id) {
+            return false;
+        }
+        let announcement = ObjectAnnouncement {
+            stream_id,
+            len: data.len() as u64,
+            block_size,
+        };
+        self.objects.push(CarouselObject {
+            announcement,
+            encoder: new_encoder(data, block_size as usize, stream_id),
+            weight,
+            priority,
+            current_weight: 0,
+            blocks_sent: 0,
+        });
+        self.pending_announcements.push_back(announcement);
+        true
+    }
+
+    /// Stops sending an object, returning false if there was no such object.
+    pub fn retire_object(&mut self, stream_id: StreamId) -> bool {
+        match self
+            .objects
+            .iter()
+            .position(|object| object.announcement.stream_id == stream_id)
+        {
+            Some(i) => {
+                self.objects.remove(i);
+                self.pending_announcements
+                    .retain(|announcement| announcement.stream_id != stream_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn has_object(&self, stream_id: StreamId) -> bool {
+        self.objects
+            .iter()
+            .any(|object| object.announcement.stream_id == stream_id)
+    }
+
+    pub fn num_objects(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Number of check blocks sent so far for an active object.
+    pub fn blocks_sent(&self, stream_id: StreamId) -> Option<u64> {
+        self.objects
+            .iter()
+            .find(|object| object.announcement.stream_id == stream_id)
+            .map(|object| object.blocks_sent)
+    }
+
+    /// The next packet to broadcast, or `None` if there are no active objects.
+    pub fn next_packet(&mut self) -> Option<CarouselPacket> {
+        if let Some(announcement) = self.pending_announcements.pop_front() {
+            return Some(CarouselPacket::Announcement(announcement));
+        }
+        if self.objects.is_empty() {
+            return None;
+        }
+        if self.packets_since_announcement >= self.announcement_interval {
+            self.packets_since_announcement = 0;
+            self.next_announcement %= self.objects.len();
+            let announcement = self.objects[self.next_announcement].announcement;
+            self.next_announcement += 1;
+            return Some(CarouselPacket::Announcement(announcement));
+        }
+        self.packets_since_announcement += 1;
+
+        let i = self.schedule();
+        let object = &mut self.objects[i];
+        match next_block(&mut object.encoder) {
+            Some((check_block_id, data)) => {
+                object.blocks_sent += 1;
+                Some(CarouselPacket::Block {
+                    stream_id: object.announcement.stream_id,
+                    check_block_id,
+                    data,
+                })
+            }
+            None => {
+                // The object has used up every check block id.
+                let stream_id = object.announcement.stream_id;
+                self.retire_object(stream_id);
+                self.next_packet()
+            }
+        }
+    }
+
+    // Picks the object to send a check block from.
+    fn schedule(&mut self) -> usize {
+        let priority = self
+            .objects
+            .iter()
+            .map(|object| object.priority)
+            .max()
+            .unwrap();
+        let mut total_weight = 0;
+        let mut chosen = 0;
+        let mut chosen_weight = i64::MIN;
+        for (i, object) in self.objects.iter_mut().enumerate() {
+            if object.priority != priority {
+                continue;
+            }
+            object.current_weight += i64::from(object.weight);
+            total_weight += i64::from(object.weight);
+            if object.current_weight > chosen_weight {
+                chosen = i;
+                chosen_weight = object.current_weight;
+            }
+        }
+        self.objects[chosen].current_weight -= total_weight;
+        chosen
+    }
+}
+
+/// Decodes every object announced on a carousel.
+pub struct CarouselReceiver {
+    decoders: HashMap<StreamId, (ObjectAnnouncement, Decoder)>,
+    // The most recently decoded objects, whose packets are ignored, and the order they were
+    // decoded in.
+    completed: HashSet<StreamId>,
+    completed_order: VecDeque<StreamId>,
+    // Objects announced as longer are ignored, as each decoder is sized from its announcement.
+    max_len: u64,
+    // Check blocks for objects which haven't been announced yet are dropped.
+    pub num_unannounced_blocks: usize,
+}
+
+impl Default for CarouselReceiver {
+    fn default() -> CarouselReceiver {
+        CarouselReceiver::new()
+    }
+}
+
+impl CarouselReceiver {
+    /// Largest object accepted by `CarouselReceiver::new`.
+    pub const DEFAULT_MAX_LEN: u64 = 1 << 30;
+    /// Number of decoded objects remembered. Older ones are forgotten, and decoded again if they
+    /// are still being broadcast.
+    pub const MAX_COMPLETED: usize = 1024;
+
+    pub fn new() -> CarouselReceiver {
+        CarouselReceiver::with_max_len(CarouselReceiver::DEFAULT_MAX_LEN)
+    }
+
+    /// Only decodes objects of up to `max_len` bytes.
+    pub fn with_max_len(max_len: u64) -> CarouselReceiver {
+        CarouselReceiver {
+            decoders: HashMap::new(),
+            completed: HashSet::new(),
+            completed_order: VecDeque::new(),
+            max_len,
+            num_unannounced_blocks: 0,
+        }
+    }
+
+    /// Returns an object's stream id and data once it has been decoded.
+    pub fn receive(&mut self, packet: CarouselPacket) -> Option<(StreamId, Vec<u8>)> {
+        match packet {
+            CarouselPacket::Announcement(announcement) => {
+                let stream_id = announcement.stream_id;
+                if self.completed.contains(&stream_id)
+                    || announcement.block_size == 0
+                    || announcement.len > self.max_len
+                {
+                    return None;
+                }
+                self.decoders.entry(stream_id).or_insert_with(|| {
+                    let decoder = new_decoder(
+                        announcement.len as usize,
+                        announcement.block_size as usize,
+                        stream_id,
+                    );
+                    (announcement, decoder)
+                });
+                None
+            }
+            CarouselPacket::Block {
+                stream_id,
+                check_block_id,
+                data,
+            } => {
+                if self.completed.contains(&stream_id) {
+                    return None;
+                }
+                let (announcement, decoder) = match self.decoders.get_mut(&stream_id) {
+                    Some(entry) => entry,
+                    None => {
+                        self.num_unannounced_blocks += 1;
+                        return None;
+                    }
+                };
+                if data.len() != announcement.block_size as usize {
+                    return None;
+                }
+                let decoded = decode_block((check_block_id, data), decoder).complete()?;
+                self.decoders.remove(&stream_id);
+                self.mark_completed(stream_id);
+                Some((stream_id, decoded))
+            }
+        }
+    }
+
+    pub fn is_complete(&self, stream_id: StreamId) -> bool {
+        self.completed.contains(&stream_id)
+    }
+
+    /// Stream ids of the objects announced but not yet decoded.
+    pub fn in_progress(&self) -> impl Iterator<Item = StreamId> + '_ {
+        self.decoders.keys().copied()
+    }
+
+    /// Drops the decoder for an object, e.g. once it is no longer being broadcast.
+    pub fn forget(&mut self, stream_id: StreamId) {
+        self.decoders.remove(&stream_id);
+        if self.completed.remove(&stream_id) {
+            self.completed_order
+                .retain(|&completed| completed != stream_id);
+        }
+    }
+
+    fn mark_completed(&mut self, stream_id: StreamId) {
+        if self.completed_order.len() == CarouselReceiver::MAX_COMPLETED {
+            if let Some(oldest) = self.completed_order.pop_front() {
+                self.completed.remove(&oldest);
+            }
+        }
+        self.completed.insert(stream_id);
+        self.completed_order.push_back(stream_id);
+    }
+}
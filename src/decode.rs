@@ -1,15 +1,97 @@
-use crate::types::{BlockIndex, CheckBlockId, StreamId};
-use crate::util::{
-    get_adjacent_blocks, get_aux_block_adjacencies, make_degree_distribution, num_aux_blocks,
-    xor_block,
-};
-use rand::distributions::WeightedIndex;
-use std::collections::{hash_map::Entry, HashMap};
-
-#[derive(Debug)]
-pub enum DecodeResult {
-    Complete(Vec<u8>),
-    InProgress(Box<Decoder>),
+use crate::adjacency::{get_adjacent_blocks_with_rng_into, DegreeDistribution};
+use crate::adjacency_cache::AdjacencyCache;
+use crate::id_set::IdSet;
+use crate::precode::{Precode, RandomPrecode};
+use crate::rng::RngAlgorithm;
+use crate::slot_lists::SlotLists;
+use crate::storage::{MemoryStorage, Storage};
+use crate::types::{BlockIndex, CheckBlockId, CodeParameters, ObjectId, StreamId};
+use crate::util::xor_block;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+use core::ops::Range;
+use hashbrown::HashMap;
+#[cfg(feature = "std")]
+use std::{fs::File, io, path::Path, time::Instant};
+
+pub enum DecodeResult<S: Storage = MemoryStorage> {
+    Complete(S::Output),
+    InProgress(Box<Decoder<S>>),
+    // A limit in the decoder's policy was hit first.
+    Incomplete(Incomplete),
+}
+
+/// What became of a check block passed to `Decoder::decode_block`.
+pub enum BlockOutcome<S: Storage = MemoryStorage> {
+    // A duplicate, or all of its adjacent blocks were already decoded.
+    Redundant,
+    // Kept until all but one of its `degree` undecoded adjacent blocks have been decoded.
+    Buffered { degree: usize },
+    // It decoded `newly_decoded` data and aux blocks, counting the ones which followed from them.
+    Progressed { newly_decoded: usize },
+    Completed(S::Output),
+    // Decoding had already completed, and the data was returned then.
+    AlreadyComplete,
+    // Held for `Decoder::make_progress`, in incremental mode.
+    Queued,
+    Rejected(RejectReason),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RejectReason {
+    WrongBlockSize,
+    // The decoder's capacity or its storage is full. Like a block of the wrong size, the block
+    // isn't taken as seen, so it can be sent again.
+    BufferFull,
+}
+
+// What a check block was used for, once its adjacent blocks were known.
+enum CheckBlockUse {
+    Redundant,
+    Decoded { num_adjacent_blocks: usize },
+    Buffered { degree: usize },
+    Dropped,
+}
+
+/// What `Decoder::make_progress` got done.
+pub enum Progress<S: Storage = MemoryStorage> {
+    Complete(S::Output),
+    // The budget ran out with work still queued.
+    Pending,
+    // Nothing is queued: more blocks are needed, or decoding has already completed.
+    Idle,
+}
+
+/// When `Decoder::into_iter` gives up on an object which isn't decoding, e.g. because too many
+/// blocks are being lost. No limits are set by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DecodePolicy {
+    // Counts every block passed in, duplicates included.
+    pub max_received_blocks: Option<u64>,
+    #[cfg(feature = "std")]
+    pub deadline: Option<Instant>,
+    // See `Decoder::buffered_bytes`.
+    pub max_buffered_bytes: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GiveUpReason {
+    MaxReceivedBlocks,
+    Deadline,
+    MaxBufferedBytes,
+}
+
+/// What was recovered of an object before giving up on it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Incomplete {
+    pub reason: GiveUpReason,
+    // The original data, with zeros where it hasn't been recovered.
+    pub data: Vec<u8>,
+    pub recovered_ranges: Vec<Range<usize>>,
 }
 
 enum UndecodedDegree {
@@ -18,34 +100,198 @@ enum UndecodedDegree {
     Many(usize),     // number of blocks that haven't yet been decoded
 }
 
+/// Memory limits for a decoder which must not grow past a fixed size, e.g. on an embedded
+/// receiver. Check blocks which would exceed the limits are dropped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecoderCapacity {
+    pub max_buffered_check_blocks: usize,
+    // Total length of the lists in `adjacent_check_blocks`.
+    pub max_buffered_adjacencies: usize,
+}
+
+/// A check block waiting in its storage slot for all but one of its adjacent blocks to be
+/// decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferedCheckBlock {
+    pub key: (StreamId, CheckBlockId),
+    // Number of adjacent blocks which haven't been decoded yet.
+    pub degree: usize,
+}
+
 #[derive(Clone, Debug)]
-pub struct Decoder {
+pub struct Decoder<S: Storage = MemoryStorage> {
     pub num_blocks: usize,
     pub num_augmented_blocks: usize,
     pub block_size: usize,
-    pub degree_distribution: WeightedIndex<f64>,
+    pub degree_distribution: DegreeDistribution,
+    // Only applies to check blocks; the precode has its own.
+    pub rng_algorithm: RngAlgorithm,
+    pub object_id: ObjectId,
+    // Stream used by `decode_block`; other streams of the same object go through
+    // `decode_stream_block`.
     pub stream_id: StreamId,
-    pub unused_aux_block_adjacencies: HashMap<BlockIndex, (usize, Vec<BlockIndex>)>,
-    pub augmented_data: Vec<u8>,
+    // The outer code's equations, indexed by aux block minus `num_blocks`: the aux block XORed
+    // with its adjacent data blocks is zero. Each holds the number of members which haven't been
+    // decoded yet, and the members (including the aux block itself). Constraints which have
+    // been used are `None`.
+    pub unused_aux_constraints: Vec<Option<(usize, Vec<BlockIndex>)>>,
+    // For each data block, the aux blocks whose constraints it is a member of.
+    pub aux_constraints_by_block: Vec<Vec<BlockIndex>>,
+    // Holds the augmented data, and the payloads of buffered check blocks.
+    pub storage: S,
     pub blocks_decoded: Vec<bool>,
     pub num_undecoded_data_blocks: usize,
-    pub unused_check_blocks: HashMap<CheckBlockId, (usize, Vec<u8>)>,
-    pub adjacent_check_blocks: HashMap<BlockIndex, Vec<CheckBlockId>>,
-    pub decode_stack: Vec<(CheckBlockId, Vec<u8>)>,
+    // Indexed by storage slot. Slots whose check block has been used are `None`, and reused once
+    // they are in `free_check_block_slots`.
+    pub check_block_slots: Vec<Option<BufferedCheckBlock>>,
+    pub free_check_block_slots: Vec<usize>,
+    pub num_buffered_check_blocks: usize,
+    // For each undecoded augmented block, the slots of the buffered check blocks adjacent to it.
+    // Entries for slots which have since been used are skipped.
+    pub adjacent_check_blocks: SlotLists,
+    // Whether to keep the blocks adjacent to each buffered check block next to its payload, at 4
+    // bytes each, rather than regenerate them once it is used. On by default.
+    pub cache_adjacencies: bool,
+    pub adjacency_cache: AdjacencyCache,
+    // Check blocks of degree one, still in their storage slots.
+    pub decode_stack: Vec<((StreamId, CheckBlockId), usize)>,
+    // When set, `decode_block` only queues check blocks, and `make_progress` decodes them a
+    // bounded amount at a time.
+    pub incremental: bool,
+    // Received check blocks which `make_progress` hasn't got to yet, in their storage slots.
+    pub pending_check_blocks: VecDeque<((StreamId, CheckBlockId), usize)>,
     pub aux_decode_stack: Vec<(BlockIndex, Vec<BlockIndex>)>,
     pub pad: usize,
+    pub capacity: Option<DecoderCapacity>,
+    pub num_dropped_check_blocks: usize,
+    // Duplicated or replayed check blocks are ignored: buffering one twice would count its
+    // neighbours twice. Only the last `IdSet::DEFAULT_WINDOW` ids of each stream are told apart,
+    // and older ones are let through, which costs time but not correctness.
+    pub seen_check_blocks: HashMap<StreamId, IdSet>,
+    pub num_duplicate_check_blocks: usize,
+    pub policy: DecodePolicy,
+    pub num_received_blocks: u64,
+    // Data and aux blocks.
+    pub num_decoded_blocks: usize,
+    // Whether the decoded data has been handed out, after which the decoder only reports
+    // `AlreadyComplete`.
+    pub completed: bool,
+    // Reused for every check block.
+    adjacent_blocks: Vec<BlockIndex>,
+    check_block: Vec<u8>,
+}
+
+impl<S: Storage> fmt::Debug for DecodeResult<S>
+where
+    S: fmt::Debug,
+    S::Output: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeResult::Complete(output) => f.debug_tuple("Complete").field(output).finish(),
+            DecodeResult::InProgress(decoder) => {
+                f.debug_tuple("InProgress").field(decoder).finish()
+            }
+            DecodeResult::Incomplete(incomplete) => {
+                f.debug_tuple("Incomplete").field(incomplete).finish()
+            }
+        }
+    }
+}
+
+impl<S: Storage> fmt::Debug for BlockOutcome<S>
+where
+    S::Output: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlockOutcome::Redundant => f.write_str("Redundant"),
+            BlockOutcome::Buffered { degree } => {
+                f.debug_struct("Buffered").field("degree", degree).finish()
+            }
+            BlockOutcome::Progressed { newly_decoded } => f
+                .debug_struct("Progressed")
+                .field("newly_decoded", newly_decoded)
+                .finish(),
+            BlockOutcome::Completed(output) => f.debug_tuple("Completed").field(output).finish(),
+            BlockOutcome::AlreadyComplete => f.write_str("AlreadyComplete"),
+            BlockOutcome::Queued => f.write_str("Queued"),
+            BlockOutcome::Rejected(reason) => f.debug_tuple("Rejected").field(reason).finish(),
+        }
+    }
+}
+
+impl<S: Storage> PartialEq for BlockOutcome<S>
+where
+    S::Output: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (BlockOutcome::Redundant, BlockOutcome::Redundant)
+            | (BlockOutcome::AlreadyComplete, BlockOutcome::AlreadyComplete)
+            | (BlockOutcome::Queued, BlockOutcome::Queued) => true,
+            (BlockOutcome::Buffered { degree: a }, BlockOutcome::Buffered { degree: b }) => a == b,
+            (
+                BlockOutcome::Progressed { newly_decoded: a },
+                BlockOutcome::Progressed { newly_decoded: b },
+            ) => a == b,
+            (BlockOutcome::Completed(a), BlockOutcome::Completed(b)) => a == b,
+            (BlockOutcome::Rejected(a), BlockOutcome::Rejected(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<S: Storage> BlockOutcome<S> {
+    /// The decoded data, if this block completed decoding.
+    pub fn complete(self) -> Option<S::Output> {
+        match self {
+            BlockOutcome::Completed(output) => Some(output),
+            _ => None,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        matches!(self, BlockOutcome::Completed(_))
+    }
+}
+
+impl CheckBlockUse {
+    fn xor_bytes(&self, block_size: usize) -> usize {
+        match self {
+            CheckBlockUse::Decoded {
+                num_adjacent_blocks,
+            } => num_adjacent_blocks * block_size,
+            // Copied into a slot.
+            CheckBlockUse::Buffered { .. } => block_size,
+            CheckBlockUse::Redundant | CheckBlockUse::Dropped => 0,
+        }
+    }
+}
+
+impl<S: Storage> fmt::Debug for Progress<S>
+where
+    S::Output: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Progress::Complete(output) => f.debug_tuple("Complete").field(output).finish(),
+            Progress::Pending => f.write_str("Pending"),
+            Progress::Idle => f.write_str("Idle"),
+        }
+    }
 }
 
-impl DecodeResult {
-    pub fn complete(self) -> Option<Vec<u8>> {
+impl<S: Storage> DecodeResult<S> {
+    pub fn complete(self) -> Option<S::Output> {
         match self {
             DecodeResult::Complete(v) => Some(v),
-            DecodeResult::InProgress(_) => None,
+            DecodeResult::InProgress(_) | DecodeResult::Incomplete(_) => None,
         }
     }
 }
 
-impl<'a> Decoder {
+impl Decoder {
     pub fn new(num_blocks: usize, block_size: usize, stream_id: StreamId, pad: usize) -> Decoder {
         Self::with_parameters(num_blocks, block_size, stream_id, 0.01, 3, pad)
     }
@@ -58,25 +304,318 @@ impl<'a> Decoder {
         q: usize,
         pad: usize,
     ) -> Decoder {
-        let num_aux_blocks = num_aux_blocks(num_blocks, epsilon, q);
+        Self::for_object(num_blocks, block_size, stream_id, epsilon, q, pad)
+    }
+
+    // Decodes an object which may be sent on several streams. `decode_block` assumes the stream
+    // id is the same as the object id.
+    pub fn for_object(
+        num_blocks: usize,
+        block_size: usize,
+        object_id: ObjectId,
+        epsilon: f64,
+        q: usize,
+        pad: usize,
+    ) -> Decoder {
+        Self::with_precode(
+            num_blocks,
+            block_size,
+            object_id,
+            epsilon,
+            &RandomPrecode::new(epsilon, q),
+            pad,
+        )
+    }
+
+    pub fn with_code_parameters(
+        num_blocks: usize,
+        block_size: usize,
+        object_id: ObjectId,
+        parameters: &CodeParameters,
+        pad: usize,
+    ) -> Decoder {
+        Self::with_degree_distribution(
+            num_blocks,
+            block_size,
+            object_id,
+            parameters,
+            &parameters.degree_distribution(),
+            pad,
+        )
+    }
+
+    // `with_code_parameters`, cloning `degree_distribution` rather than building it. It must be
+    // `parameters.degree_distribution()`, whose tables are then shared, so that decoders for many
+    // objects don't each build an alias table.
+    pub fn with_degree_distribution(
+        num_blocks: usize,
+        block_size: usize,
+        object_id: ObjectId,
+        parameters: &CodeParameters,
+        degree_distribution: &DegreeDistribution,
+        pad: usize,
+    ) -> Decoder {
+        let precode = RandomPrecode::with_code_parameters(parameters);
+        let mut decoder = Self::with_precode(
+            num_blocks,
+            block_size,
+            object_id,
+            parameters.epsilon,
+            &precode,
+            pad,
+        );
+        decoder.use_code_parameters(parameters, degree_distribution);
+        decoder
+    }
+
+    // The precode must be the one the encoder used.
+    pub fn with_precode<P: Precode + ?Sized>(
+        num_blocks: usize,
+        block_size: usize,
+        object_id: ObjectId,
+        epsilon: f64,
+        precode: &P,
+        pad: usize,
+    ) -> Decoder {
+        let num_augmented_blocks = num_blocks + precode.num_aux_blocks(num_blocks);
+        let storage = MemoryStorage::new(num_augmented_blocks * block_size);
+        Decoder::with_precode_and_storage(
+            num_blocks, block_size, object_id, epsilon, precode, pad, storage,
+        )
+    }
+
+    // Buffers are allocated up front, and kept within `capacity` from then on, so that decoding
+    // check blocks from `object_id`'s own stream doesn't allocate.
+    pub fn with_capacity(
+        num_blocks: usize,
+        block_size: usize,
+        object_id: ObjectId,
+        epsilon: f64,
+        q: usize,
+        pad: usize,
+        capacity: DecoderCapacity,
+    ) -> Decoder {
+        let parameters = CodeParameters {
+            epsilon,
+            q,
+            ..CodeParameters::default()
+        };
+        Self::with_code_parameters_and_capacity(
+            num_blocks,
+            block_size,
+            object_id,
+            &parameters,
+            pad,
+            capacity,
+        )
+    }
+
+    // `with_capacity` for any code parameters.
+    pub fn with_code_parameters_and_capacity(
+        num_blocks: usize,
+        block_size: usize,
+        object_id: ObjectId,
+        parameters: &CodeParameters,
+        pad: usize,
+        capacity: DecoderCapacity,
+    ) -> Decoder {
+        let mut decoder =
+            Self::with_code_parameters(num_blocks, block_size, object_id, parameters, pad);
+        decoder.check_block_slots = Vec::with_capacity(capacity.max_buffered_check_blocks);
+        decoder.free_check_block_slots = Vec::with_capacity(capacity.max_buffered_check_blocks);
+        decoder.adjacent_check_blocks = SlotLists::with_capacity(
+            decoder.num_augmented_blocks,
+            capacity.max_buffered_adjacencies,
+        );
+        decoder.decode_stack = Vec::with_capacity(capacity.max_buffered_check_blocks);
+        decoder.pending_check_blocks = VecDeque::with_capacity(capacity.max_buffered_check_blocks);
+        decoder.aux_decode_stack = Vec::with_capacity(decoder.num_augmented_blocks - num_blocks);
+        decoder.adjacent_blocks = Vec::with_capacity(decoder.degree_distribution.max_degree());
+        decoder.check_block = Vec::with_capacity(block_size);
+        // Check blocks from any other stream add an `IdSet` each.
+        decoder.seen_check_blocks.insert(object_id, IdSet::new());
+        // Cached adjacencies would need allocating as check blocks arrive.
+        decoder.cache_adjacencies = false;
+        if let Some(last_slot) = capacity.max_buffered_check_blocks.checked_sub(1) {
+            decoder.storage.check_block_slot(last_slot, block_size);
+        }
+        decoder.capacity = Some(capacity);
+        decoder
+    }
+
+    pub fn into_incomplete_result(mut self) -> (Vec<bool>, Vec<u8>) {
+        self.blocks_decoded.truncate(self.num_blocks);
+        let mut data = self
+            .storage
+            .finish(self.num_blocks * self.block_size, self.pad);
+        data.truncate(self.num_blocks * self.block_size);
+        (self.blocks_decoded, data)
+    }
+
+    /// Length of the augmented data, which a `Storage` passed to `with_storage` must hold.
+    pub fn storage_len(num_blocks: usize, block_size: usize, epsilon: f64, q: usize) -> usize {
+        let parameters = CodeParameters {
+            epsilon,
+            q,
+            ..CodeParameters::default()
+        };
+        Self::storage_len_for(num_blocks, block_size, &parameters)
+    }
+
+    /// `storage_len` for `with_code_parameters_and_storage`.
+    pub fn storage_len_for(
+        num_blocks: usize,
+        block_size: usize,
+        parameters: &CodeParameters,
+    ) -> usize {
+        let precode = RandomPrecode::with_code_parameters(parameters);
+        (num_blocks + precode.num_aux_blocks(num_blocks)) * block_size
+    }
+}
+
+impl<'a, S: Storage> Decoder<S> {
+    // Decodes into `storage`, whose augmented data must be `storage_len` bytes of zeros.
+    pub fn with_storage(
+        num_blocks: usize,
+        block_size: usize,
+        object_id: ObjectId,
+        epsilon: f64,
+        q: usize,
+        pad: usize,
+        storage: S,
+    ) -> Decoder<S> {
+        let parameters = CodeParameters {
+            epsilon,
+            q,
+            ..CodeParameters::default()
+        };
+        Self::with_code_parameters_and_storage(
+            num_blocks,
+            block_size,
+            object_id,
+            &parameters,
+            pad,
+            storage,
+        )
+    }
+
+    // Decodes into `storage`, whose augmented data must be `storage_len_for` bytes of zeros.
+    pub fn with_code_parameters_and_storage(
+        num_blocks: usize,
+        block_size: usize,
+        object_id: ObjectId,
+        parameters: &CodeParameters,
+        pad: usize,
+        storage: S,
+    ) -> Decoder<S> {
+        let precode = RandomPrecode::with_code_parameters(parameters);
+        let mut decoder = Self::with_precode_and_storage(
+            num_blocks,
+            block_size,
+            object_id,
+            parameters.epsilon,
+            &precode,
+            pad,
+            storage,
+        );
+        decoder.use_code_parameters(parameters, &parameters.degree_distribution());
+        decoder
+    }
+
+    // The storage's augmented data must be `num_blocks + precode.num_aux_blocks(num_blocks)`
+    // blocks of zeros.
+    pub fn with_precode_and_storage<P: Precode + ?Sized>(
+        num_blocks: usize,
+        block_size: usize,
+        object_id: ObjectId,
+        epsilon: f64,
+        precode: &P,
+        pad: usize,
+        storage: S,
+    ) -> Decoder<S> {
+        let aux_block_members = precode.aux_block_members(object_id, num_blocks);
+        let num_aux_blocks = aux_block_members.len();
         let num_augmented_blocks = num_blocks + num_aux_blocks;
-        let unused_aux_block_adjacencies =
-            get_aux_block_adjacencies(stream_id, num_blocks, num_aux_blocks, q);
+        assert_eq!(
+            storage.augmented_data().len(),
+            num_augmented_blocks * block_size
+        );
+        let mut unused_aux_constraints = Vec::with_capacity(num_aux_blocks);
+        let mut aux_constraints_by_block = vec![Vec::new(); num_blocks];
+        let mut aux_decode_stack = Vec::new();
+        for (aux_block_index, mut members) in (num_blocks..).zip(aux_block_members) {
+            for block_index in &members {
+                aux_constraints_by_block[*block_index].push(aux_block_index);
+            }
+            members.push(aux_block_index);
+            if members.len() == 1 {
+                // An aux block with no adjacent data blocks is all zeros.
+                aux_decode_stack.push((aux_block_index, members));
+                unused_aux_constraints.push(None);
+            } else {
+                unused_aux_constraints.push(Some((members.len(), members)));
+            }
+        }
         Decoder {
             num_blocks,
             num_augmented_blocks,
             block_size,
-            unused_aux_block_adjacencies,
-            degree_distribution: make_degree_distribution(epsilon),
-            stream_id,
-            augmented_data: vec![0; num_augmented_blocks * block_size],
+            unused_aux_constraints,
+            aux_constraints_by_block,
+            degree_distribution: DegreeDistribution::new(epsilon),
+            rng_algorithm: RngAlgorithm::default(),
+            object_id,
+            stream_id: object_id,
+            storage,
             blocks_decoded: vec![false; num_augmented_blocks],
             num_undecoded_data_blocks: num_blocks,
-            unused_check_blocks: HashMap::new(),
-            adjacent_check_blocks: HashMap::new(),
+            check_block_slots: Vec::new(),
+            free_check_block_slots: Vec::new(),
+            num_buffered_check_blocks: 0,
+            adjacent_check_blocks: SlotLists::new(num_augmented_blocks),
+            cache_adjacencies: true,
+            adjacency_cache: AdjacencyCache::new(),
             decode_stack: Vec::new(),
-            aux_decode_stack: Vec::new(),
-            pad: pad,
+            incremental: false,
+            pending_check_blocks: VecDeque::new(),
+            aux_decode_stack,
+            pad,
+            capacity: None,
+            num_dropped_check_blocks: 0,
+            seen_check_blocks: HashMap::new(),
+            num_duplicate_check_blocks: 0,
+            policy: DecodePolicy::default(),
+            num_received_blocks: 0,
+            num_decoded_blocks: 0,
+            completed: false,
+            adjacent_blocks: Vec::new(),
+            check_block: Vec::new(),
+        }
+    }
+
+    // `degree_distribution` must be `parameters.degree_distribution()`.
+    fn use_code_parameters(
+        &mut self,
+        parameters: &CodeParameters,
+        degree_distribution: &DegreeDistribution,
+    ) {
+        self.rng_algorithm = parameters.rng_algorithm;
+        self.degree_distribution = if parameters.adapt_to_small_objects {
+            degree_distribution.adapted_to(self.num_augmented_blocks)
+        } else {
+            degree_distribution.clone()
+        };
+    }
+
+    fn has_room_for(&self, degree: usize) -> bool {
+        match self.capacity {
+            Some(capacity) => {
+                self.num_buffered_check_blocks + self.pending_check_blocks.len()
+                    < capacity.max_buffered_check_blocks
+                    && self.adjacent_check_blocks.len() + degree
+                        <= capacity.max_buffered_adjacencies
+            }
+            None => true,
         }
     }
 
@@ -84,131 +623,572 @@ impl<'a> Decoder {
         &mut self,
         check_block_id: CheckBlockId,
         check_block: &[u8],
-    ) -> Option<Vec<u8>> {
+    ) -> BlockOutcome<S> {
+        self.decode_stream_block(self.stream_id, check_block_id, check_block)
+    }
+
+    pub fn decode_stream_block(
+        &mut self,
+        stream_id: StreamId,
+        check_block_id: CheckBlockId,
+        check_block: &[u8],
+    ) -> BlockOutcome<S> {
+        self.decode_check_block(stream_id, check_block_id, check_block, None)
+    }
+
+    /// `decode_stream_block` for a check block whose adjacent blocks the caller has worked out,
+    /// for codes such as the sliding window one in `stream`. They can't be regenerated, so the
+    /// decoder must cache adjacencies, and can't be in incremental mode.
+    pub fn decode_block_with_adjacency(
+        &mut self,
+        stream_id: StreamId,
+        check_block_id: CheckBlockId,
+        check_block: &[u8],
+        adjacent_blocks: &[BlockIndex],
+    ) -> BlockOutcome<S> {
+        assert!(
+            self.cache_adjacencies && !self.incremental,
+            "adjacencies from the caller can't be regenerated"
+        );
+        self.decode_check_block(
+            stream_id,
+            check_block_id,
+            check_block,
+            Some(adjacent_blocks),
+        )
+    }
+
+    fn decode_check_block(
+        &mut self,
+        stream_id: StreamId,
+        check_block_id: CheckBlockId,
+        check_block: &[u8],
+        adjacent_blocks: Option<&[BlockIndex]>,
+    ) -> BlockOutcome<S> {
+        if self.completed {
+            return BlockOutcome::AlreadyComplete;
+        }
         if self.num_undecoded_data_blocks == 0 {
-            // Decoding has already finished and the decoded data has already been returned.
-            return None;
+            // An empty object, which is complete before any check block arrives.
+            return BlockOutcome::Completed(self.finish());
+        }
+        self.num_received_blocks += 1;
+        if check_block.len() != self.block_size {
+            return BlockOutcome::Rejected(RejectReason::WrongBlockSize);
+        }
+        if self.has_seen(stream_id, check_block_id) {
+            self.num_duplicate_check_blocks += 1;
+            return BlockOutcome::Redundant;
+        }
+
+        let check_block_key = (stream_id, check_block_id);
+        if self.incremental {
+            return if self.enqueue_check_block(check_block_key, check_block) {
+                self.mark_seen(check_block_key);
+                BlockOutcome::Queued
+            } else {
+                BlockOutcome::Rejected(RejectReason::BufferFull)
+            };
+        }
+        // Blocks queued while in incremental mode go first, as they would have.
+        while self.num_undecoded_data_blocks > 0 && self.step().is_some() {}
+        if self.num_undecoded_data_blocks == 0 {
+            return BlockOutcome::Completed(self.finish());
+        }
+        let num_decoded_before = self.num_decoded_blocks;
+        let check_block_use = match adjacent_blocks {
+            Some(adjacent_blocks) => {
+                self.use_check_block(check_block_key, check_block, adjacent_blocks)
+            }
+            None => self.process_check_block(check_block_key, check_block),
+        };
+        // A dropped check block may be sent again once there is room for it.
+        if !matches!(check_block_use, CheckBlockUse::Dropped) {
+            self.mark_seen(check_block_key);
+        }
+        while self.num_undecoded_data_blocks > 0 && self.step().is_some() {}
+        if self.num_undecoded_data_blocks == 0 {
+            return BlockOutcome::Completed(self.finish());
+        }
+        match check_block_use {
+            CheckBlockUse::Redundant => BlockOutcome::Redundant,
+            CheckBlockUse::Decoded { .. } => BlockOutcome::Progressed {
+                newly_decoded: self.num_decoded_blocks - num_decoded_before,
+            },
+            CheckBlockUse::Buffered { degree } => BlockOutcome::Buffered { degree },
+            CheckBlockUse::Dropped => BlockOutcome::Rejected(RejectReason::BufferFull),
+        }
+    }
+
+    /// In incremental mode, does queued decoding work until about `max_xor_bytes` bytes have been
+    /// XORed or copied. Every step counts as at least one block, and the last step may overshoot
+    /// the budget. Decodes exactly as `decode_block` would have.
+    pub fn make_progress(&mut self, max_xor_bytes: usize) -> Progress<S> {
+        if self.completed {
+            return Progress::Idle;
         }
+        if self.num_undecoded_data_blocks == 0 {
+            return Progress::Complete(self.finish());
+        }
+        let mut xor_bytes = 0;
+        while xor_bytes < max_xor_bytes {
+            match self.step() {
+                Some(step_bytes) => xor_bytes += usize::max(step_bytes, self.block_size),
+                None => return Progress::Idle,
+            }
+            if self.num_undecoded_data_blocks == 0 {
+                return Progress::Complete(self.finish());
+            }
+        }
+        if self.has_pending_work() {
+            Progress::Pending
+        } else {
+            Progress::Idle
+        }
+    }
+
+    /// Whether `make_progress` has anything left to do.
+    pub fn has_pending_work(&self) -> bool {
+        self.num_undecoded_data_blocks > 0
+            && !(self.decode_stack.is_empty()
+                && self.aux_decode_stack.is_empty()
+                && self.pending_check_blocks.is_empty())
+    }
+
+    // Keeps the check block in a storage slot until `make_progress` gets to it, returning false if
+    // there is no room.
+    fn enqueue_check_block(
+        &mut self,
+        check_block_key: (StreamId, CheckBlockId),
+        check_block: &[u8],
+    ) -> bool {
+        let slot = if self.has_room_for(0) {
+            self.allocate_check_block_slot(check_block)
+        } else {
+            None
+        };
+        match slot {
+            Some(slot) => {
+                self.adjacency_cache.remove(slot);
+                self.pending_check_blocks.push_back((check_block_key, slot));
+                true
+            }
+            None => {
+                self.num_dropped_check_blocks += 1;
+                false
+            }
+        }
+    }
+
+    // Does the next piece of decoding work, returning how many bytes it XORed or copied, or
+    // `None` if there is nothing to do. Received blocks are only started on once everything they
+    // could have caused has been done.
+    fn step(&mut self) -> Option<usize> {
+        if let Some((check_block_key, slot)) = self.decode_stack.pop() {
+            let check_block_use = self.process_buffered_check_block(check_block_key, slot);
+            Some(check_block_use.xor_bytes(self.block_size))
+        } else if let Some((_, members)) = self.aux_decode_stack.pop() {
+            // All but one member of the constraint are known, so the last one is the XOR of
+            // the others.
+            if let Some(target_block_index) = block_to_decode(&members, &self.blocks_decoded) {
+                xor_adjacent_blocks(
+                    target_block_index,
+                    &members,
+                    self.storage.augmented_data_mut(),
+                    self.block_size,
+                );
+                self.mark_decoded(target_block_index);
+                Some((members.len() - 1) * self.block_size)
+            } else {
+                Some(0)
+            }
+        } else if let Some((check_block_key, slot)) = self.pending_check_blocks.pop_front() {
+            let check_block_use = self.process_buffered_check_block(check_block_key, slot);
+            if matches!(check_block_use, CheckBlockUse::Dropped) {
+                let (stream_id, check_block_id) = check_block_key;
+                if let Some(ids) = self.seen_check_blocks.get_mut(&stream_id) {
+                    ids.remove(check_block_id);
+                }
+            }
+            Some(check_block_use.xor_bytes(self.block_size))
+        } else {
+            None
+        }
+    }
+
+    fn finish(&mut self) -> S::Output {
+        self.completed = true;
+        // Whatever is still queued isn't needed.
+        self.decode_stack = Vec::new();
+        self.aux_decode_stack = Vec::new();
+        self.pending_check_blocks = VecDeque::new();
+        self.free_check_block_slots = Vec::new();
+        self.adjacency_cache = AdjacencyCache::new();
+        self.storage
+            .finish(self.block_size * self.num_blocks, self.pad)
+    }
+
+    /// Whether a check block has already been used or buffered. Only the last
+    /// `IdSet::DEFAULT_WINDOW` ids below the highest id seen on a stream are remembered, and older
+    /// ids are reported as unseen.
+    pub fn has_seen(&self, stream_id: StreamId, check_block_id: CheckBlockId) -> bool {
+        self.seen_check_blocks
+            .get(&stream_id)
+            .is_some_and(|ids| ids.contains(check_block_id))
+    }
+
+    fn mark_seen(&mut self, check_block_key: (StreamId, CheckBlockId)) {
+        let (stream_id, check_block_id) = check_block_key;
+        self.seen_check_blocks
+            .entry(stream_id)
+            .or_default()
+            .insert(check_block_id);
+    }
+
+    fn process_check_block(
+        &mut self,
+        check_block_key: (StreamId, CheckBlockId),
+        check_block: &[u8],
+    ) -> CheckBlockUse {
+        let (stream_id, check_block_id) = check_block_key;
+        let mut adjacent_blocks = mem::take(&mut self.adjacent_blocks);
+        get_adjacent_blocks_with_rng_into(
+            self.rng_algorithm,
+            check_block_id,
+            stream_id,
+            &self.degree_distribution,
+            self.num_augmented_blocks,
+            &mut adjacent_blocks,
+        );
+        let check_block_use = self.use_check_block(check_block_key, check_block, &adjacent_blocks);
+        self.adjacent_blocks = adjacent_blocks;
+        check_block_use
+    }
+
+    // Uses a check block from the decode stack, and frees its slot.
+    fn process_buffered_check_block(
+        &mut self,
+        check_block_key: (StreamId, CheckBlockId),
+        slot: usize,
+    ) -> CheckBlockUse {
+        let mut check_block = mem::take(&mut self.check_block);
+        check_block.clear();
+        check_block.extend_from_slice(
+            self.storage
+                .check_block_slot(slot, self.block_size)
+                .expect("buffered check block slot went missing"),
+        );
+        self.free_check_block_slots.push(slot);
+        let mut adjacent_blocks = mem::take(&mut self.adjacent_blocks);
+        let check_block_use = if self.adjacency_cache.take(slot, &mut adjacent_blocks) {
+            let check_block_use =
+                self.use_check_block(check_block_key, &check_block, &adjacent_blocks);
+            self.adjacent_blocks = adjacent_blocks;
+            check_block_use
+        } else {
+            self.adjacent_blocks = adjacent_blocks;
+            self.process_check_block(check_block_key, &check_block)
+        };
+        self.check_block = check_block;
+        check_block_use
+    }
 
-        // TODO: don't immediately push then pop off the decode stack
-        self.decode_stack
-            .push((check_block_id, check_block.to_owned()));
-
-        while let Some((check_block_id, check_block)) = self.decode_stack.pop() {
-            let adjacent_blocks = get_adjacent_blocks(
-                check_block_id,
-                self.stream_id,
-                &self.degree_distribution,
-                self.num_augmented_blocks,
-            );
-            match undecoded_degree(&adjacent_blocks, &self.blocks_decoded) {
-                UndecodedDegree::Zero => { /* This check block contains no new information. */ }
-                UndecodedDegree::One(target_block_index) => {
-                    decode_from_check_block(
-                        target_block_index,
-                        &check_block,
-                        &adjacent_blocks,
-                        &mut self.augmented_data,
-                        self.block_size,
-                    );
-                    self.blocks_decoded[target_block_index] = true;
-                    if target_block_index < self.num_blocks {
-                        self.num_undecoded_data_blocks -= 1;
-                    } else {
-                        // Decoded an aux block.
-                        // If that aux block can be used to decode a data block, schedule it for
-                        // decoding.
-                        if let Entry::Occupied(mut unused_aux_entry) =
-                            self.unused_aux_block_adjacencies.entry(target_block_index)
-                        {
-                            let remaining_degree = &mut unused_aux_entry.get_mut().0;
-                            *remaining_degree -= 1;
-                            if *remaining_degree == 1 {
-                                self.aux_decode_stack
-                                    .push((target_block_index, unused_aux_entry.remove().1));
-                            }
-                        }
+    fn use_check_block(
+        &mut self,
+        check_block_key: (StreamId, CheckBlockId),
+        check_block: &[u8],
+        adjacent_blocks: &[BlockIndex],
+    ) -> CheckBlockUse {
+        match undecoded_degree(adjacent_blocks, &self.blocks_decoded) {
+            UndecodedDegree::Zero => {
+                // This check block contains no new information.
+                CheckBlockUse::Redundant
+            }
+            UndecodedDegree::One(target_block_index) => {
+                decode_from_check_block(
+                    target_block_index,
+                    check_block,
+                    adjacent_blocks,
+                    self.storage.augmented_data_mut(),
+                    self.block_size,
+                );
+                self.mark_decoded(target_block_index);
+                CheckBlockUse::Decoded {
+                    num_adjacent_blocks: adjacent_blocks.len(),
+                }
+            }
+            UndecodedDegree::Many(degree) => {
+                if !self.has_room_for(degree) {
+                    self.num_dropped_check_blocks += 1;
+                    return CheckBlockUse::Dropped;
+                }
+                let slot = match self.allocate_check_block_slot(check_block) {
+                    Some(slot) => slot,
+                    None => {
+                        self.num_dropped_check_blocks += 1;
+                        return CheckBlockUse::Dropped;
                     }
-                    if let Some(adjacent_check_block_ids) =
-                        self.adjacent_check_blocks.remove(&target_block_index)
-                    {
-                        for check_block_id in adjacent_check_block_ids {
-                            if let Entry::Occupied(mut unused_block_entry) =
-                                self.unused_check_blocks.entry(check_block_id)
-                            {
-                                let remaining_degree = &mut unused_block_entry.get_mut().0;
-                                *remaining_degree -= 1;
-                                if *remaining_degree == 1 {
-                                    self.decode_stack.push((
-                                        check_block_id,
-                                        unused_block_entry.remove().1.to_owned(),
-                                    ));
-                                }
-                            }
-                        }
-                    };
+                };
+                self.check_block_slots[slot] = Some(BufferedCheckBlock {
+                    key: check_block_key,
+                    degree,
+                });
+                self.num_buffered_check_blocks += 1;
+                if self.cache_adjacencies {
+                    self.adjacency_cache.insert(slot, adjacent_blocks);
+                } else {
+                    // Whatever the slot's last check block left behind.
+                    self.adjacency_cache.remove(slot);
                 }
-                UndecodedDegree::Many(degree) => {
-                    self.unused_check_blocks
-                        .insert(check_block_id, (degree, check_block.to_owned()));
-                    for block_index in adjacent_blocks {
-                        self.adjacent_check_blocks
-                            .entry(block_index)
-                            .or_default()
-                            .push(check_block_id)
+                for &block_index in adjacent_blocks {
+                    // Only undecoded blocks can lower the check block's degree.
+                    if !self.blocks_decoded[block_index] {
+                        self.adjacent_check_blocks.push(block_index, slot);
                     }
                 }
+                CheckBlockUse::Buffered { degree }
             }
         }
+    }
 
-        while let Some((aux_block_index, adjacent_blocks)) = self.aux_decode_stack.pop() {
-            if let Some(decoded_block_id) = decode_aux_block(
-                aux_block_index,
-                &adjacent_blocks,
-                &mut self.augmented_data,
-                self.block_size,
-                &self.blocks_decoded,
-            ) {
-                self.blocks_decoded[decoded_block_id] = true;
-                self.num_undecoded_data_blocks -= 1;
+    // Copies the check block into a free storage slot, unless the storage is full. Lists only
+    // ever refer to a freed slot until its check block has been used, as that decodes the last
+    // block whose list could still hold it.
+    fn allocate_check_block_slot(&mut self, check_block: &[u8]) -> Option<usize> {
+        let slot = self
+            .free_check_block_slots
+            .pop()
+            .unwrap_or(self.check_block_slots.len());
+        match self.storage.check_block_slot(slot, self.block_size) {
+            Some(buffer) => buffer.copy_from_slice(check_block),
+            None => {
+                if slot < self.check_block_slots.len() {
+                    self.free_check_block_slots.push(slot);
+                }
+                return None;
             }
         }
+        if slot == self.check_block_slots.len() {
+            self.check_block_slots.push(None);
+        }
+        Some(slot)
+    }
 
-        if self.num_undecoded_data_blocks == 0 {
-            // Decoding finished -- return decoded data.
-            let mut decoded_data = std::mem::take(&mut self.augmented_data);
-            decoded_data.truncate(self.block_size * self.num_blocks);
-            Some(decoded_data)
+    // Schedules every check block and aux constraint which this block leaves with a single
+    // unknown member.
+    fn mark_decoded(&mut self, block_index: BlockIndex) {
+        self.blocks_decoded[block_index] = true;
+        self.num_decoded_blocks += 1;
+        if block_index < self.num_blocks {
+            self.num_undecoded_data_blocks -= 1;
+            for aux_block_index in core::mem::take(&mut self.aux_constraints_by_block[block_index])
+            {
+                self.reduce_aux_constraint(aux_block_index);
+            }
         } else {
-            // Decoding not yet complete.
-            None
+            self.reduce_aux_constraint(block_index);
+        }
+
+        while let Some(slot) = self.adjacent_check_blocks.pop(block_index) {
+            if let Some(buffered) = &mut self.check_block_slots[slot] {
+                buffered.degree -= 1;
+                if buffered.degree == 1 {
+                    // The slot is freed once the check block has been used.
+                    self.decode_stack.push((buffered.key, slot));
+                    self.check_block_slots[slot] = None;
+                    self.num_buffered_check_blocks -= 1;
+                }
+            }
+        }
+    }
+
+    // Counts one more known member of the aux block's constraint.
+    fn reduce_aux_constraint(&mut self, aux_block_index: BlockIndex) {
+        let constraint = &mut self.unused_aux_constraints[aux_block_index - self.num_blocks];
+        if let Some((remaining_degree, _)) = constraint {
+            *remaining_degree -= 1;
+            if *remaining_degree == 1 {
+                let (_, members) = constraint.take().unwrap();
+                self.aux_decode_stack.push((aux_block_index, members));
+            }
+        }
+    }
+
+    /// Makes `blocks` undecoded zeros again, so that they can be decoded afresh with different
+    /// data, as the sliding window code in `stream` does. Buffered check blocks adjacent to any
+    /// of them are dropped. Only for decoders without an outer code, which cache adjacencies and
+    /// have no decoding work queued.
+    pub fn forget_blocks(&mut self, blocks: &[BlockIndex]) {
+        assert!(
+            self.num_blocks == self.num_augmented_blocks
+                && self.cache_adjacencies
+                && !self.has_pending_work(),
+            "blocks can't be forgotten by this decoder"
+        );
+        let mut adjacent_blocks = mem::take(&mut self.adjacent_blocks);
+        for slot in 0..self.check_block_slots.len() {
+            if self.check_block_slots[slot].is_none()
+                || !self.adjacency_cache.get(slot, &mut adjacent_blocks)
+                || !adjacent_blocks
+                    .iter()
+                    .any(|block_index| blocks.contains(block_index))
+            {
+                continue;
+            }
+            for &block_index in &adjacent_blocks {
+                if !self.blocks_decoded[block_index] {
+                    self.adjacent_check_blocks.remove(block_index, slot);
+                }
+            }
+            self.adjacency_cache.remove(slot);
+            self.check_block_slots[slot] = None;
+            self.num_buffered_check_blocks -= 1;
+            self.free_check_block_slots.push(slot);
+        }
+        self.adjacent_blocks = adjacent_blocks;
+
+        for &block_index in blocks {
+            if self.blocks_decoded[block_index] {
+                self.blocks_decoded[block_index] = false;
+                self.num_decoded_blocks -= 1;
+                self.num_undecoded_data_blocks += 1;
+            }
+            let start = block_index * self.block_size;
+            for byte in &mut self.storage.augmented_data_mut()[start..start + self.block_size] {
+                *byte = 0;
+            }
         }
     }
 
-    pub fn into_iter<T>(mut self, iter: T) -> DecodeResult
+    pub fn into_iter<T>(mut self, iter: T) -> DecodeResult<S>
     where
         T: IntoIterator<Item = (CheckBlockId, &'a [u8])>,
     {
         for (check_block_id, check_block) in iter {
-            if let Some(decoded_data) = self.decode_block(check_block_id, check_block) {
+            if let BlockOutcome::Completed(decoded_data) =
+                self.decode_block(check_block_id, check_block)
+            {
                 return DecodeResult::Complete(decoded_data);
             }
+            if let Some(reason) = self.check_policy() {
+                return DecodeResult::Incomplete(self.give_up(reason));
+            }
         }
         DecodeResult::InProgress(Box::new(self))
     }
 
+    /// Memory held for check blocks which couldn't be used yet: their payloads and the lists of
+    /// blocks adjacent to them.
+    pub fn buffered_bytes(&self) -> usize {
+        self.num_buffered_check_blocks
+            * (self.block_size + mem::size_of::<Option<BufferedCheckBlock>>())
+            + self.pending_check_blocks.len() * self.block_size
+            + self.adjacent_check_blocks.len() * SlotLists::ENTRY_SIZE
+            + self.adjacency_cache.len() * mem::size_of::<u32>()
+    }
+
+    /// The first limit in `policy` which has been reached, if any. Callers feeding blocks in
+    /// themselves check this after each one. There are none once all the data is decoded.
+    pub fn check_policy(&self) -> Option<GiveUpReason> {
+        if self.num_undecoded_data_blocks == 0 {
+            return None;
+        }
+        if let Some(max_received_blocks) = self.policy.max_received_blocks {
+            if self.num_received_blocks >= max_received_blocks {
+                return Some(GiveUpReason::MaxReceivedBlocks);
+            }
+        }
+        #[cfg(feature = "std")]
+        if let Some(deadline) = self.policy.deadline {
+            if Instant::now() >= deadline {
+                return Some(GiveUpReason::Deadline);
+            }
+        }
+        if let Some(max_buffered_bytes) = self.policy.max_buffered_bytes {
+            if self.buffered_bytes() > max_buffered_bytes {
+                return Some(GiveUpReason::MaxBufferedBytes);
+            }
+        }
+        None
+    }
+
+    /// Ends decoding early, keeping whatever has been recovered. Not for decoders which have
+    /// already handed out the decoded data.
+    pub fn give_up(self, reason: GiveUpReason) -> Incomplete {
+        assert!(
+            !self.completed,
+            "the decoded data has already been returned"
+        );
+        let recovered_ranges = self.recovered_ranges();
+        let mut data = vec![0; self.original_len()];
+        for range in &recovered_ranges {
+            data[range.clone()].copy_from_slice(&self.storage.augmented_data()[range.clone()]);
+        }
+        Incomplete {
+            reason,
+            data,
+            recovered_ranges,
+        }
+    }
+
     pub fn get_incomplete_result(&self) -> (&[bool], &[u8]) {
         (
             &self.blocks_decoded[0..self.num_blocks],
-            &self.augmented_data[0..self.block_size * self.num_blocks],
+            &self.storage.augmented_data()[0..self.block_size * self.num_blocks],
         )
     }
 
-    pub fn into_incomplete_result(mut self) -> (Vec<bool>, Vec<u8>) {
-        self.blocks_decoded.truncate(self.num_blocks);
-        self.augmented_data
-            .truncate(self.num_blocks * self.block_size);
-        (self.blocks_decoded, self.augmented_data)
+    /// Length of the data before it was padded to a whole number of blocks.
+    pub fn original_len(&self) -> usize {
+        self.num_blocks * self.block_size - self.pad
+    }
+
+    /// Byte ranges of the original data which have been decoded so far, in order.
+    pub fn recovered_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (block_index, _) in self.blocks_decoded[0..self.num_blocks]
+            .iter()
+            .enumerate()
+            .filter(|(_, decoded)| **decoded)
+        {
+            let start = block_index * self.block_size;
+            let end = usize::min(start + self.block_size, self.original_len());
+            match ranges.last_mut() {
+                Some(last) if last.end == start => last.end = end,
+                _ => ranges.push(start..end),
+            }
+        }
+        ranges.retain(|range| !range.is_empty());
+        ranges
+    }
+
+    /// Writes the recovered ranges at their offsets, leaving the rest of `out` untouched.
+    ///
+    /// Once decoding has completed the data has been handed out by `decode_block` instead.
+    #[cfg(feature = "std")]
+    pub fn write_recovered<W: io::Write + io::Seek>(&self, out: &mut W) -> io::Result<()> {
+        if self.storage.augmented_data().len() < self.num_blocks * self.block_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "decoded data has already been returned",
+            ));
+        }
+        for range in self.recovered_ranges() {
+            out.seek(io::SeekFrom::Start(range.start as u64))?;
+            out.write_all(&self.storage.augmented_data()[range])?;
+        }
+        Ok(())
+    }
+
+    /// Creates a file of the original length holding the recovered ranges. Unrecovered ranges
+    /// are never written, so they read as zeros and are left as holes on filesystems with sparse
+    /// file support.
+    #[cfg(feature = "std")]
+    pub fn write_sparse_file<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<Range<usize>>> {
+        let mut file = File::create(path)?;
+        file.set_len(self.original_len() as u64)?;
+        self.write_recovered(&mut file)?;
+        Ok(self.recovered_ranges())
     }
 }
 
@@ -232,28 +1212,6 @@ fn decode_from_check_block(
     );
 }
 
-fn decode_aux_block(
-    index: BlockIndex,
-    adjacent_blocks: &[BlockIndex],
-    augmented_data: &mut [u8],
-    block_size: usize,
-    blocks_decoded: &[bool],
-) -> Option<BlockIndex> {
-    block_to_decode(adjacent_blocks, blocks_decoded).map(|target_block_index| {
-        for i in 0..block_size {
-            augmented_data[target_block_index * block_size + i] ^=
-                augmented_data[index * block_size + i];
-        }
-        xor_adjacent_blocks(
-            target_block_index,
-            adjacent_blocks,
-            augmented_data,
-            block_size,
-        );
-        target_block_index
-    })
-}
-
 fn xor_adjacent_blocks(
     target_block_index: BlockIndex,
     adjacent_blocks: &[BlockIndex],
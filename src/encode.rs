@@ -1,14 +1,20 @@
+use crate::adjacency::DegreeDistribution;
 use crate::block_iter::BlockIter;
-use crate::types::StreamId;
-use crate::util::{
-    make_degree_distribution, sample_with_exclusive_repeats, seed_stream_rng, xor_block,
-};
+use crate::precode::{Precode, RandomPrecode};
+use crate::rng::RngAlgorithm;
+use crate::types::{CodeParameters, ObjectId, StreamId};
+use crate::util::xor_block;
+use alloc::vec;
+use alloc::vec::Vec;
 
 #[derive(Clone, Debug)]
-pub struct OnlineCoder {
+pub struct OnlineCoder<P: Precode = RandomPrecode> {
     block_size: usize,
-    epsilon: f64,
-    q: usize,
+    degree_distribution: DegreeDistribution,
+    // Whether to adapt the degree distribution to each object's size as it is encoded.
+    adapt_to_small_objects: bool,
+    precode: P,
+    rng_algorithm: RngAlgorithm,
 }
 
 impl OnlineCoder {
@@ -17,32 +23,66 @@ impl OnlineCoder {
     }
 
     pub fn with_parameters(block_size: usize, epsilon: f64, q: usize) -> OnlineCoder {
+        OnlineCoder::with_precode(block_size, epsilon, RandomPrecode::new(epsilon, q))
+    }
+
+    pub fn with_code_parameters(block_size: usize, parameters: &CodeParameters) -> OnlineCoder {
+        OnlineCoder {
+            block_size,
+            degree_distribution: DegreeDistribution::new(parameters.epsilon)
+                .with_sampler(parameters.degree_sampler),
+            adapt_to_small_objects: parameters.adapt_to_small_objects,
+            precode: RandomPrecode::with_code_parameters(parameters),
+            rng_algorithm: parameters.rng_algorithm,
+        }
+    }
+}
+
+impl<P: Precode> OnlineCoder<P> {
+    pub fn with_precode(block_size: usize, epsilon: f64, precode: P) -> OnlineCoder<P> {
         OnlineCoder {
             block_size,
-            epsilon,
-            q,
+            degree_distribution: DegreeDistribution::new(epsilon),
+            adapt_to_small_objects: false,
+            precode,
+            rng_algorithm: RngAlgorithm::default(),
         }
     }
 
+    /// Adapts the degree distribution to objects smaller than its maximum degree, which changes
+    /// their check blocks. Decoders must do the same, and small objects decode with less overhead.
+    pub fn with_small_object_adaptation(mut self) -> OnlineCoder<P> {
+        self.adapt_to_small_objects = true;
+        self
+    }
+
     pub fn encode(&self, data: Vec<u8>, stream_id: StreamId) -> BlockIter {
-        let aux_data = self.outer_encode(&data, stream_id);
-        self.inner_encode(data, aux_data, stream_id)
+        self.encode_object(data, stream_id, stream_id)
     }
 
-    fn num_aux_blocks(&self, num_blocks: usize) -> usize {
-        (0.55_f64 * self.q as f64 * self.epsilon * num_blocks as f64).ceil() as usize
+    // Check blocks from every stream of the same object can be fed to a single decoder.
+    pub fn encode_object(
+        &self,
+        data: Vec<u8>,
+        object_id: ObjectId,
+        stream_id: StreamId,
+    ) -> BlockIter {
+        let aux_data = self.outer_encode(&data, object_id);
+        self.inner_encode(data, aux_data, stream_id)
     }
 
-    fn outer_encode(&self, data: &[u8], stream_id: StreamId) -> Vec<u8> {
+    pub fn outer_encode(&self, data: &[u8], object_id: ObjectId) -> Vec<u8> {
         let num_blocks = data.len() / self.block_size;
-        let num_aux_blocks = self.num_aux_blocks(num_blocks);
-        let mut aux_data = vec![0; num_aux_blocks * self.block_size];
-        let mut rng = seed_stream_rng(stream_id);
-        for block in data.chunks_exact(self.block_size) {
-            for aux_index in sample_with_exclusive_repeats(&mut rng, num_aux_blocks, self.q) {
+        let aux_block_members = self.precode.aux_block_members(object_id, num_blocks);
+        let mut aux_data = vec![0; aux_block_members.len() * self.block_size];
+        for (aux_block, members) in aux_data
+            .chunks_exact_mut(self.block_size)
+            .zip(aux_block_members)
+        {
+            for block_index in members {
                 xor_block(
-                    &mut aux_data[aux_index * self.block_size..],
-                    block,
+                    aux_block,
+                    &data[block_index * self.block_size..],
                     self.block_size,
                 );
             }
@@ -51,13 +91,21 @@ impl OnlineCoder {
     }
 
     fn inner_encode(&self, data: Vec<u8>, aux_data: Vec<u8>, stream_id: StreamId) -> BlockIter {
+        let degree_distribution = if self.adapt_to_small_objects {
+            let num_augmented_blocks = (data.len() + aux_data.len()) / self.block_size;
+            self.degree_distribution.adapted_to(num_augmented_blocks)
+        } else {
+            self.degree_distribution.clone()
+        };
         BlockIter {
             data,
             aux_data,
             block_size: self.block_size,
-            degree_distribution: make_degree_distribution(self.epsilon),
+            degree_distribution,
+            rng_algorithm: self.rng_algorithm,
             check_block_id: 0,
             stream_id,
+            adjacent_blocks: Vec::new(),
         }
     }
 }
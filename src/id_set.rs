@@ -0,0 +1,100 @@
+use crate::types::CheckBlockId;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The check block ids seen lately, as a bitmap over a sliding window which ends at the highest
+/// id seen, so the memory used stays at one bit per id of the window however long a stream runs.
+/// Ids which have fallen behind the window can't be told apart and count as unseen, so a late
+/// block is let through rather than thrown away.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdSet {
+    // Bit `id % window` is set if `id` was seen, for the ids in the window.
+    bits: Vec<u64>,
+    window: u64,
+    highest: Option<CheckBlockId>,
+}
+
+impl Default for IdSet {
+    fn default() -> IdSet {
+        IdSet::new()
+    }
+}
+
+impl IdSet {
+    /// Number of ids remembered by `IdSet::new`, in 8 KiB.
+    pub const DEFAULT_WINDOW: usize = 1 << 16;
+
+    pub fn new() -> IdSet {
+        IdSet::with_window(IdSet::DEFAULT_WINDOW)
+    }
+
+    /// Remembers the last `window` ids, rounded up to a multiple of 64.
+    pub fn with_window(window: usize) -> IdSet {
+        assert!(window > 0, "window must not be empty");
+        let words = window.div_ceil(64);
+        IdSet {
+            bits: vec![0; words],
+            window: 64 * words as u64,
+            highest: None,
+        }
+    }
+
+    pub fn contains(&self, id: CheckBlockId) -> bool {
+        match self.highest {
+            Some(highest) if id <= highest => {
+                highest - id < self.window && self.bits[self.word(id)] & bit(id) != 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Adds `id`, returning whether it was new. Ids behind the window are always new, and aren't
+    /// remembered.
+    pub fn insert(&mut self, id: CheckBlockId) -> bool {
+        if self.contains(id) {
+            return false;
+        }
+        match self.highest {
+            Some(highest) if id <= highest && highest - id >= self.window => return true,
+            Some(highest) if id <= highest => {}
+            Some(highest) if id - highest < self.window => {
+                // Clear the ids which the window is moving onto.
+                for newer in highest + 1..id {
+                    let word = self.word(newer);
+                    self.bits[word] &= !bit(newer);
+                }
+                self.highest = Some(id);
+            }
+            _ => {
+                self.bits.iter_mut().for_each(|word| *word = 0);
+                self.highest = Some(id);
+            }
+        }
+        let word = self.word(id);
+        self.bits[word] |= bit(id);
+        true
+    }
+
+    /// Forgets `id`, unless it has already fallen behind the window.
+    pub fn remove(&mut self, id: CheckBlockId) {
+        if let Some(highest) = self.highest {
+            if id <= highest && highest - id < self.window {
+                let word = self.word(id);
+                self.bits[word] &= !bit(id);
+            }
+        }
+    }
+
+    /// Number of ids remembered.
+    pub fn window(&self) -> usize {
+        self.window as usize
+    }
+
+    fn word(&self, id: CheckBlockId) -> usize {
+        ((id % self.window) / 64) as usize
+    }
+}
+
+fn bit(id: CheckBlockId) -> u64 {
+    1 << (id % 64)
+}
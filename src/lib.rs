@@ -1,17 +1,38 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
 use block_iter::BlockIter;
-use decode::Decoder;
-use types::{CheckBlockId, StreamId};
+use decode::{BlockOutcome, Decoder};
+use types::{CheckBlockId, ObjectId, StreamId};
 
-mod block_iter;
+pub mod adjacency;
+pub mod adjacency_cache;
+pub mod block_iter;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod carousel;
 pub mod decode;
-mod encode;
+pub mod encode;
+pub mod id_set;
+pub mod precode;
+#[cfg(feature = "std")]
+pub mod redundancy;
+pub mod rng;
+#[cfg(feature = "std")]
+pub mod sim;
+pub mod slot_lists;
+pub mod storage;
+pub mod stream;
 pub mod types;
+#[cfg(feature = "std")]
+pub mod udp;
 mod util;
 
 // TODO: use larger seeds for the PRNG
 // TODO: allow specification of starting block_id
 // TODO: write more tests with proptest
-// TODO: write benchmarks with criterion
 // TODO: profile and fix low-hanging fruit
 // TODO: write docs
 
@@ -23,43 +44,78 @@ pub struct Encoder {
 
 pub type Block = (CheckBlockId, Vec<u8>);
 
-pub fn new_encoder(mut buf: Vec<u8>, block_size: usize, stream_id: StreamId) -> Encoder {
-    let len = buf.len();
+fn padding(len: usize, block_size: usize) -> usize {
     let rem = len % block_size;
     let pad: usize = match rem {
         0 => 0,
         r => block_size - r,
     };
     assert!(pad < block_size);
-    buf.resize_with(len + pad, || 0);
+    pad
+}
+
+pub fn new_encoder(mut buf: Vec<u8>, block_size: usize, stream_id: StreamId) -> Encoder {
+    let len = buf.len();
+    buf.resize_with(len + padding(len, block_size), || 0);
     let coder = encode::OnlineCoder::new(block_size);
     let block_iter = coder.encode(buf, stream_id);
     Encoder { block_iter }
 }
 
+// Encoders for the same object on different streams produce check blocks which complement each
+// other, rather than duplicates.
+pub fn new_object_encoder(
+    mut buf: Vec<u8>,
+    block_size: usize,
+    object_id: ObjectId,
+    stream_id: StreamId,
+) -> Encoder {
+    let len = buf.len();
+    buf.resize_with(len + padding(len, block_size), || 0);
+    let coder = encode::OnlineCoder::new(block_size);
+    let block_iter = coder.encode_object(buf, object_id, stream_id);
+    Encoder { block_iter }
+}
+
 pub fn new_decoder(buf_len: usize, block_size: usize, stream_id: StreamId) -> Decoder {
-    let len = buf_len;
-    let rem = len % block_size;
-    let pad: usize = match rem {
-        0 => 0,
-        r => block_size - r,
-    };
-    assert!(pad < block_size);
+    let pad = padding(buf_len, block_size);
     Decoder::new((buf_len + pad) / block_size, block_size, stream_id, pad)
 }
 
+// Blocks from any stream of the object can be fed in with `decode_stream_block`.
+pub fn new_object_decoder(buf_len: usize, block_size: usize, object_id: ObjectId) -> Decoder {
+    let pad = padding(buf_len, block_size);
+    Decoder::for_object(
+        (buf_len + pad) / block_size,
+        block_size,
+        object_id,
+        0.01,
+        3,
+        pad,
+    )
+}
+
 pub fn next_block(encoder: &mut Encoder) -> Option<Block> {
     encoder.block_iter.next()
 }
 
-pub fn decode_block(block: Block, decoder: &mut Decoder) -> Option<Vec<u8>> {
-    match decoder.decode_block(block.0, &block.1) {
-        Some(mut block) => {
+pub fn decode_block(block: Block, decoder: &mut Decoder) -> BlockOutcome {
+    let stream_id = decoder.stream_id;
+    decode_stream_block(stream_id, block, decoder)
+}
+
+pub fn decode_stream_block(
+    stream_id: StreamId,
+    block: Block,
+    decoder: &mut Decoder,
+) -> BlockOutcome {
+    match decoder.decode_stream_block(stream_id, block.0, &block.1) {
+        BlockOutcome::Completed(mut block) => {
             let pad = decoder.pad;
             let len = block.len();
-            block.resize(len - pad as usize, 0);
-            Some(block)
+            block.resize(len - pad, 0);
+            BlockOutcome::Completed(block)
         }
-        None => None,
+        outcome => outcome,
     }
 }
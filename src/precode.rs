@@ -0,0 +1,150 @@
+//! Outer codes ("precodes"), which add aux blocks to the data before the inner code, so that
+//! the data can be recovered even if the inner code leaves a few blocks undecoded. Each aux
+//! block is the XOR of some of the data blocks; the encoder and decoder must use the same
+//! precode.
+
+use crate::adjacency::{get_aux_block_members, num_aux_blocks};
+use crate::rng::RngAlgorithm;
+use crate::types::{BlockIndex, CodeParameters, ObjectId};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+pub trait Precode: Debug {
+    fn num_aux_blocks(&self, num_blocks: usize) -> usize;
+
+    /// The data blocks XORed into each of the `num_aux_blocks` aux blocks.
+    fn aux_block_members(&self, object_id: ObjectId, num_blocks: usize) -> Vec<Vec<BlockIndex>>;
+}
+
+impl<P: Precode + ?Sized> Precode for &P {
+    fn num_aux_blocks(&self, num_blocks: usize) -> usize {
+        (**self).num_aux_blocks(num_blocks)
+    }
+
+    fn aux_block_members(&self, object_id: ObjectId, num_blocks: usize) -> Vec<Vec<BlockIndex>> {
+        (**self).aux_block_members(object_id, num_blocks)
+    }
+}
+
+/// No aux blocks, which makes the online code a plain LT code.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NoPrecode;
+
+impl Precode for NoPrecode {
+    fn num_aux_blocks(&self, _num_blocks: usize) -> usize {
+        0
+    }
+
+    fn aux_block_members(&self, _object_id: ObjectId, _num_blocks: usize) -> Vec<Vec<BlockIndex>> {
+        Vec::new()
+    }
+}
+
+/// The outer code from the Maymounkov-Mazières paper: each data block is XORed into `q` aux
+/// blocks chosen at random, out of `0.55 * q * epsilon * num_blocks`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RandomPrecode {
+    pub epsilon: f64,
+    pub q: usize,
+    pub rng_algorithm: RngAlgorithm,
+    // Leaves out the aux blocks when there would be no more of them than each data block goes
+    // into, as they would be (nearly) the same sum of all the data blocks. Off by default, as it
+    // changes the code of small objects.
+    pub adapt_to_small_objects: bool,
+}
+
+impl RandomPrecode {
+    pub fn new(epsilon: f64, q: usize) -> RandomPrecode {
+        Self::with_rng(epsilon, q, RngAlgorithm::default())
+    }
+
+    pub fn with_rng(epsilon: f64, q: usize, rng_algorithm: RngAlgorithm) -> RandomPrecode {
+        RandomPrecode {
+            epsilon,
+            q,
+            rng_algorithm,
+            adapt_to_small_objects: false,
+        }
+    }
+
+    pub fn with_code_parameters(parameters: &CodeParameters) -> RandomPrecode {
+        RandomPrecode {
+            adapt_to_small_objects: parameters.adapt_to_small_objects,
+            ..Self::with_rng(parameters.epsilon, parameters.q, parameters.rng_algorithm)
+        }
+    }
+}
+
+impl Default for RandomPrecode {
+    fn default() -> RandomPrecode {
+        RandomPrecode::new(0.01, 3)
+    }
+}
+
+impl Precode for RandomPrecode {
+    fn num_aux_blocks(&self, num_blocks: usize) -> usize {
+        let num_aux_blocks = num_aux_blocks(num_blocks, self.epsilon, self.q);
+        if self.adapt_to_small_objects && num_aux_blocks <= self.q {
+            0
+        } else {
+            num_aux_blocks
+        }
+    }
+
+    fn aux_block_members(&self, object_id: ObjectId, num_blocks: usize) -> Vec<Vec<BlockIndex>> {
+        get_aux_block_members(
+            self.rng_algorithm,
+            object_id,
+            num_blocks,
+            self.num_aux_blocks(num_blocks),
+            self.q,
+        )
+    }
+}
+
+/// The structured LDPC code of Raptor codes (RFC 5053, section 5.4.2.3). Every data block goes
+/// into 3 aux blocks picked by a fixed pattern rather than at random, spreading them evenly over
+/// the aux blocks. It doesn't depend on the object id.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LdpcPrecode;
+
+fn is_prime(n: usize) -> bool {
+    n >= 2
+        && (2..)
+            .take_while(|d| d * d <= n)
+            .all(|d| !n.is_multiple_of(d))
+}
+
+impl Precode for LdpcPrecode {
+    fn num_aux_blocks(&self, num_blocks: usize) -> usize {
+        if num_blocks == 0 {
+            return 0;
+        }
+        // The smallest prime at least ceil(0.01 * K) + X, where X is the smallest positive
+        // integer with X * (X - 1) >= 2 * K.
+        let mut x = 1;
+        while x * (x - 1) < 2 * num_blocks {
+            x += 1;
+        }
+        let mut s = num_blocks.div_ceil(100) + x;
+        while !is_prime(s) {
+            s += 1;
+        }
+        s
+    }
+
+    fn aux_block_members(&self, _object_id: ObjectId, num_blocks: usize) -> Vec<Vec<BlockIndex>> {
+        let s = self.num_aux_blocks(num_blocks);
+        let mut members = vec![Vec::new(); s];
+        for i in 0..num_blocks {
+            let a = 1 + (i / s) % (s - 1);
+            let b = i % s;
+            // `s` is a prime greater than 2, so these are distinct.
+            members[b].push(i);
+            members[(b + a) % s].push(i);
+            members[(b + 2 * a) % s].push(i);
+        }
+        members
+    }
+}
@@ -0,0 +1,201 @@
+//! How many check blocks a sender without feedback should send, so that a receiver behind a
+//! lossy channel decodes with at least a given probability.
+//!
+//! Losses are modelled as independent with a fixed rate, so the number of blocks received out of
+//! `n` sent is binomial. The number of blocks the decoder needs comes either from the analytic
+//! bound of the paper, `(1 + epsilon)` times the number of augmented blocks, or from running the
+//! real encoder and `Decoder` without losses, which also captures the variation between objects.
+//! The two are independent because erasures don't depend on the contents of check blocks.
+
+use crate::precode::{Precode, RandomPrecode};
+use crate::sim::{ErasureChannel, Simulation};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// The parameters can't change once simulations have been cached.
+#[derive(Debug)]
+pub struct RedundancyCalculator {
+    epsilon: f64,
+    q: usize,
+    // Simulated objects per message size; success probabilities are resolved to `1 / trials`.
+    trials: usize,
+    seed: u64,
+    // Received blocks each simulated object needed, by number of blocks. `None` if it didn't
+    // decode within the simulation's overhead limit.
+    needed_blocks: Mutex<HashMap<usize, Arc<Vec<Option<usize>>>>>,
+}
+
+impl RedundancyCalculator {
+    pub fn new(epsilon: f64, q: usize) -> RedundancyCalculator {
+        Self::with_trials(epsilon, q, 200, 0)
+    }
+
+    pub fn with_trials(epsilon: f64, q: usize, trials: usize, seed: u64) -> RedundancyCalculator {
+        RedundancyCalculator {
+            epsilon,
+            q,
+            trials,
+            seed,
+            needed_blocks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The paper's bound on the received blocks needed: `(1 + epsilon)` times the number of
+    /// augmented blocks. It holds for large messages; small ones usually need more.
+    pub fn analytic_needed_blocks(&self, num_blocks: usize) -> usize {
+        let precode = RandomPrecode::new(self.epsilon, self.q);
+        let num_augmented_blocks = num_blocks + precode.num_aux_blocks(num_blocks);
+        ((1.0 + self.epsilon) * num_augmented_blocks as f64).ceil() as usize
+    }
+
+    /// Received blocks needed by each of `trials` simulated objects, simulated once per
+    /// `num_blocks` and then cached.
+    pub fn simulated_needed_blocks(&self, num_blocks: usize) -> Arc<Vec<Option<usize>>> {
+        if let Some(needed_blocks) = self.needed_blocks.lock().unwrap().get(&num_blocks) {
+            return needed_blocks.clone();
+        }
+        // Without losses, and with one byte blocks: the contents don't affect decoding. Small
+        // objects can need several times their size, which is part of what's being measured.
+        let report = Simulation {
+            epsilon: self.epsilon,
+            q: self.q,
+            trials: self.trials,
+            seed: self.seed,
+            max_overhead: 4.0,
+            ..Simulation::new(num_blocks, 1, ErasureChannel::Bernoulli { loss: 0.0 })
+        }
+        .run();
+        let needed_blocks: Arc<Vec<_>> = Arc::new(
+            report
+                .trials
+                .iter()
+                .map(|trial| trial.completed.then_some(trial.blocks_received))
+                .collect(),
+        );
+        self.needed_blocks
+            .lock()
+            .unwrap()
+            .entry(num_blocks)
+            .or_insert(needed_blocks)
+            .clone()
+    }
+
+    /// Check blocks to send with the analytic model. `None` if `p` can't be reached.
+    pub fn analytic_blocks_to_send(
+        &self,
+        len: usize,
+        block_size: usize,
+        loss: f64,
+        p: f64,
+    ) -> Option<usize> {
+        let num_blocks = len.div_ceil(block_size);
+        blocks_to_send(&[Some(self.analytic_needed_blocks(num_blocks))], loss, p)
+    }
+
+    /// Check blocks to send according to simulations of the real decoder. `None` if `p` can't
+    /// be reached, e.g. because some simulated objects never decoded.
+    pub fn simulated_blocks_to_send(
+        &self,
+        len: usize,
+        block_size: usize,
+        loss: f64,
+        p: f64,
+    ) -> Option<usize> {
+        let num_blocks = len.div_ceil(block_size);
+        if num_blocks == 0 {
+            return Some(0);
+        }
+        blocks_to_send(&self.simulated_needed_blocks(num_blocks), loss, p)
+    }
+}
+
+impl Default for RedundancyCalculator {
+    fn default() -> RedundancyCalculator {
+        RedundancyCalculator::new(0.01, 3)
+    }
+}
+
+/// Probability that at least `needed` of `sent` blocks get through, each being lost
+/// independently with probability `loss`.
+pub fn probability_received(sent: usize, needed: usize, loss: f64) -> f64 {
+    received_tail(sent, loss)
+        .get(needed)
+        .copied()
+        .unwrap_or(0.0)
+}
+
+// Entry `k` is the probability that at least `k` of `sent` blocks are received.
+fn received_tail(sent: usize, loss: f64) -> Vec<f64> {
+    let mut tail = vec![0.0; sent + 2];
+    if loss <= 0.0 {
+        tail[..=sent].fill(1.0);
+        return tail;
+    }
+    if loss >= 1.0 {
+        tail[0] = 1.0;
+        return tail;
+    }
+    // The binomial distribution in log space, as the probabilities at the ends underflow.
+    let log_odds = (1.0 - loss).ln() - loss.ln();
+    let mut log_probability = sent as f64 * loss.ln();
+    let mut probabilities = Vec::with_capacity(sent + 1);
+    for k in 0..=sent {
+        probabilities.push(log_probability.exp());
+        log_probability += ((sent - k) as f64 / (k + 1) as f64).ln() + log_odds;
+    }
+    for k in (0..=sent).rev() {
+        tail[k] = tail[k + 1] + probabilities[k];
+    }
+    for probability in &mut tail {
+        *probability = probability.min(1.0);
+    }
+    tail
+}
+
+fn success_probability(sent: usize, needed_blocks: &[Option<usize>], loss: f64) -> f64 {
+    let tail = received_tail(sent, loss);
+    let total: f64 = needed_blocks
+        .iter()
+        .flatten()
+        .map(|&needed| tail.get(needed).copied().unwrap_or(0.0))
+        .sum();
+    total / needed_blocks.len() as f64
+}
+
+/// The fewest check blocks to send so that an object needing a number of received blocks drawn
+/// from `needed_blocks` is decoded with probability at least `p`.
+pub fn blocks_to_send(needed_blocks: &[Option<usize>], loss: f64, p: f64) -> Option<usize> {
+    if p <= 0.0 {
+        return Some(0);
+    }
+    if needed_blocks.is_empty() || loss >= 1.0 {
+        return None;
+    }
+    // What sending ever more blocks tends to. With losses it is only reached in the limit.
+    let limit = needed_blocks.iter().flatten().count() as f64 / needed_blocks.len() as f64;
+    if limit < p || limit == p && loss > 0.0 {
+        return None;
+    }
+    let mut low = 0;
+    let mut high = needed_blocks
+        .iter()
+        .flatten()
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    while success_probability(high, needed_blocks, loss) < p {
+        low = high;
+        high *= 2;
+    }
+    // Success only gets more likely as more blocks are sent.
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if success_probability(mid, needed_blocks, loss) >= p {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Some(high)
+}
@@ -0,0 +1,273 @@
+//! The pseudorandom generators adjacency is drawn from. Which one a code uses is part of its
+//! parameters, identified on the wire by `RngAlgorithm::id`, and each is specified exactly here
+//! so that other implementations can reproduce it bit for bit.
+
+use crate::types::{CheckBlockId, StreamId};
+use core::fmt;
+
+pub trait AdjacencyRng {
+    fn next_u64(&mut self) -> u64;
+
+    /// Uniform sample from `0..n`, using Lemire's multiply-and-reject method.
+    fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "cannot sample from an empty range");
+        let n = n as u64;
+        let mut m = u128::from(self.next_u64()) * u128::from(n);
+        if (m as u64) < n {
+            let threshold = n.wrapping_neg() % n;
+            while (m as u64) < threshold {
+                m = u128::from(self.next_u64()) * u128::from(n);
+            }
+        }
+        (m >> 64) as usize
+    }
+}
+
+/// A 256-bit key shared by the sender and receivers of a keyed stream. It is never sent, and
+/// never printed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SecretKey([u8; 32]);
+
+impl SecretKey {
+    pub fn new(bytes: [u8; 32]) -> SecretKey {
+        SecretKey(bytes)
+    }
+
+    // Little-endian words, as ChaCha keys are read.
+    fn words(&self) -> [u32; 8] {
+        let mut words = [0; 8];
+        for (word, bytes) in words.iter_mut().zip(self.0.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        words
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+/// Identifies a generator. New generators get new ids; existing ones never change.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RngAlgorithm {
+    // Fast, and the original choice.
+    #[default]
+    Xoshiro256StarStar,
+    // Slower, but of cryptographic quality.
+    ChaCha8,
+    // ChaCha8 used as a PRF under a secret key, so that only holders of the key can tell which
+    // blocks a check block covers, and an attacker can't pick which ones to erase.
+    KeyedChaCha8(SecretKey),
+}
+
+impl RngAlgorithm {
+    pub fn id(self) -> u8 {
+        match self {
+            RngAlgorithm::Xoshiro256StarStar => 0,
+            RngAlgorithm::ChaCha8 => 1,
+            RngAlgorithm::KeyedChaCha8(_) => 2,
+        }
+    }
+
+    /// Keyed algorithms also need the key, see `from_keyed_id`.
+    pub fn from_id(id: u8) -> Option<RngAlgorithm> {
+        match id {
+            0 => Some(RngAlgorithm::Xoshiro256StarStar),
+            1 => Some(RngAlgorithm::ChaCha8),
+            _ => None,
+        }
+    }
+
+    pub fn from_keyed_id(id: u8, key: SecretKey) -> Option<RngAlgorithm> {
+        match id {
+            2 => Some(RngAlgorithm::KeyedChaCha8(key)),
+            _ => None,
+        }
+    }
+
+    pub fn is_keyed(self) -> bool {
+        matches!(self, RngAlgorithm::KeyedChaCha8(_))
+    }
+
+    /// The generator for one check block of a stream.
+    pub fn rng(self, stream_id: StreamId, check_block_id: CheckBlockId) -> AlgorithmRng {
+        match self {
+            RngAlgorithm::Xoshiro256StarStar => {
+                AlgorithmRng::Xoshiro(XoshiroRng::new(stream_id, check_block_id))
+            }
+            RngAlgorithm::ChaCha8 => AlgorithmRng::ChaCha(ChaChaRng::new(
+                &ChaChaRng::stream_key(&[0; 8], stream_id),
+                check_block_id,
+            )),
+            RngAlgorithm::KeyedChaCha8(key) => AlgorithmRng::ChaCha(ChaChaRng::new(
+                &ChaChaRng::stream_key(&key.words(), stream_id),
+                check_block_id,
+            )),
+        }
+    }
+}
+
+/// Any of the built-in generators.
+#[derive(Clone, Debug)]
+pub enum AlgorithmRng {
+    Xoshiro(XoshiroRng),
+    ChaCha(ChaChaRng),
+}
+
+impl AdjacencyRng for AlgorithmRng {
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            AlgorithmRng::Xoshiro(rng) => rng.next_u64(),
+            AlgorithmRng::ChaCha(rng) => rng.next_u64(),
+        }
+    }
+}
+
+// SplitMix64 output function: a bijection which spreads every input bit over the output.
+fn mix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// xoshiro256** (Blackman and Vigna), seeded from a stream id and check block id.
+#[derive(Clone, Debug)]
+pub struct XoshiroRng {
+    s: [u64; 4],
+}
+
+impl XoshiroRng {
+    pub fn new(stream_id: StreamId, check_block_id: CheckBlockId) -> XoshiroRng {
+        // Keep all the bits of both ids, so that streams never share check blocks, and make sure
+        // the seed is a good, even mix of 0's and 1's. The first output depends only on s1, so
+        // both ids go into it.
+        let s0 = mix64(stream_id);
+        let s1 = mix64(check_block_id ^ 0x6a09_e667_f3bc_c909 ^ s0);
+        let s2 = mix64(s0 ^ s1);
+        let s3 = mix64(s2);
+        XoshiroRng {
+            s: [s0, s1, s2, s3],
+        }
+    }
+}
+
+impl AdjacencyRng for XoshiroRng {
+    fn next_u64(&mut self) -> u64 {
+        let result = self.s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.s[1] << 17;
+        self.s[2] ^= self.s[0];
+        self.s[3] ^= self.s[1];
+        self.s[1] ^= self.s[2];
+        self.s[0] ^= self.s[3];
+        self.s[2] ^= t;
+        self.s[3] = self.s[3].rotate_left(45);
+        result
+    }
+}
+
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(16);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(12);
+    x[a] = x[a].wrapping_add(x[b]);
+    x[d] = (x[d] ^ x[a]).rotate_left(8);
+    x[c] = x[c].wrapping_add(x[d]);
+    x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
+
+/// The ChaCha block function with the RFC 8439 layout: a 32-bit block counter and a 96-bit
+/// nonce. `rounds` is 20 for ChaCha20 and 8 for ChaCha8.
+pub fn chacha_block(key: &[u32; 8], counter: u32, nonce: &[u32; 3], rounds: usize) -> [u32; 16] {
+    let mut state = [0; 16];
+    state[..4].copy_from_slice(&[0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574]);
+    state[4..12].copy_from_slice(key);
+    state[12] = counter;
+    state[13..].copy_from_slice(nonce);
+    let mut x = state;
+    for _ in 0..rounds / 2 {
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 1, 5, 9, 13);
+        quarter_round(&mut x, 2, 6, 10, 14);
+        quarter_round(&mut x, 3, 7, 11, 15);
+        quarter_round(&mut x, 0, 5, 10, 15);
+        quarter_round(&mut x, 1, 6, 11, 12);
+        quarter_round(&mut x, 2, 7, 8, 13);
+        quarter_round(&mut x, 3, 4, 9, 14);
+    }
+    for (x, state) in x.iter_mut().zip(state.iter()) {
+        *x = x.wrapping_add(*state);
+    }
+    x
+}
+
+fn split_u64(value: u64) -> [u32; 2] {
+    [value as u32, (value >> 32) as u32]
+}
+
+/// Counter-based ChaCha8. Each check block gets its own keystream, with the check block id as
+/// the nonce, under a key specific to the stream. Unkeyed streams derive it from the all-zero
+/// key. Output words are consumed in order, two per `u64`, low word first.
+#[derive(Clone)]
+pub struct ChaChaRng {
+    key: [u32; 8],
+    nonce: [u32; 3],
+    counter: u32,
+    block: [u32; 16],
+    index: usize,
+}
+
+impl ChaChaRng {
+    const ROUNDS: usize = 8;
+
+    /// Derives a stream's key from a 256-bit key: the first 8 words of the block with counter 0
+    /// and the stream id as the nonce, whose last word is 1 to keep it apart from check blocks.
+    pub fn stream_key(key: &[u32; 8], stream_id: StreamId) -> [u32; 8] {
+        let [low, high] = split_u64(stream_id);
+        let block = chacha_block(key, 0, &[low, high, 1], Self::ROUNDS);
+        let mut stream_key = [0; 8];
+        stream_key.copy_from_slice(&block[..8]);
+        stream_key
+    }
+
+    pub fn new(stream_key: &[u32; 8], check_block_id: CheckBlockId) -> ChaChaRng {
+        let [low, high] = split_u64(check_block_id);
+        ChaChaRng {
+            key: *stream_key,
+            nonce: [low, high, 0],
+            counter: 0,
+            block: [0; 16],
+            index: 16,
+        }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.index == 16 {
+            self.block = chacha_block(&self.key, self.counter, &self.nonce, Self::ROUNDS);
+            self.counter = self.counter.wrapping_add(1);
+            self.index = 0;
+        }
+        self.index += 1;
+        self.block[self.index - 1]
+    }
+}
+
+// The key may be derived from a secret one.
+impl fmt::Debug for ChaChaRng {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChaChaRng")
+            .field("nonce", &self.nonce)
+            .field("counter", &self.counter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AdjacencyRng for ChaChaRng {
+    fn next_u64(&mut self) -> u64 {
+        let low = self.next_u32();
+        u64::from(low) | (u64::from(self.next_u32()) << 32)
+    }
+}
@@ -0,0 +1,272 @@
+use crate::decode::Decoder;
+use crate::encode::OnlineCoder;
+use crate::precode::{Precode, RandomPrecode};
+use rand::{Rng, RngCore};
+use rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256StarStar;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Models which check blocks are erased on the way to the decoder.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErasureChannel {
+    // Every block is lost independently with the given probability.
+    Bernoulli {
+        loss: f64,
+    },
+    // Two-state burst channel: the chance of moving between states is drawn per block, and each
+    // state has its own loss probability.
+    GilbertElliott {
+        good_to_bad: f64,
+        bad_to_good: f64,
+        good_loss: f64,
+        bad_loss: f64,
+    },
+    // Repeats the pattern; `true` means the block is lost.
+    Pattern(Vec<bool>),
+}
+
+#[derive(Clone, Debug)]
+pub struct ChannelState {
+    channel: ErasureChannel,
+    in_bad_state: bool,
+    position: usize,
+}
+
+impl ChannelState {
+    pub fn new(channel: ErasureChannel) -> ChannelState {
+        ChannelState {
+            channel,
+            in_bad_state: false,
+            position: 0,
+        }
+    }
+
+    /// Returns whether the next block is erased.
+    pub fn is_erased<R: RngCore>(&mut self, rng: &mut R) -> bool {
+        match &self.channel {
+            ErasureChannel::Bernoulli { loss } => rng.gen::<f64>() < *loss,
+            ErasureChannel::GilbertElliott {
+                good_to_bad,
+                bad_to_good,
+                good_loss,
+                bad_loss,
+            } => {
+                let switch = if self.in_bad_state {
+                    bad_to_good
+                } else {
+                    good_to_bad
+                };
+                if rng.gen::<f64>() < *switch {
+                    self.in_bad_state = !self.in_bad_state;
+                }
+                let loss = if self.in_bad_state {
+                    bad_loss
+                } else {
+                    good_loss
+                };
+                rng.gen::<f64>() < *loss
+            }
+            ErasureChannel::Pattern(pattern) => {
+                let erased = !pattern.is_empty() && pattern[self.position % pattern.len()];
+                self.position += 1;
+                erased
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Simulation {
+    pub num_blocks: usize,
+    pub block_size: usize,
+    pub epsilon: f64,
+    pub q: usize,
+    // See `CodeParameters::adapt_to_small_objects`.
+    pub adapt_to_small_objects: bool,
+    // Replaces the random precode given by `epsilon` and `q`.
+    pub precode: Option<Arc<dyn Precode>>,
+    pub channel: ErasureChannel,
+    pub trials: usize,
+    pub seed: u64,
+    // A trial fails if it needs more than `num_blocks * (1 + max_overhead)` received blocks.
+    pub max_overhead: f64,
+    // A trial also fails once it has sent `max_sent_per_received` times that many blocks, so that
+    // a channel which erases (nearly) everything can't stall it.
+    pub max_sent_per_received: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrialResult {
+    pub blocks_sent: usize,
+    pub blocks_received: usize,
+    pub completed: bool,
+    // Time spent inside the decoder, excluding encoding and the channel.
+    pub decode_time: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub num_blocks: usize,
+    pub trials: Vec<TrialResult>,
+}
+
+impl Simulation {
+    pub fn new(num_blocks: usize, block_size: usize, channel: ErasureChannel) -> Simulation {
+        Simulation {
+            num_blocks,
+            block_size,
+            epsilon: 0.01,
+            q: 3,
+            adapt_to_small_objects: false,
+            precode: None,
+            channel,
+            trials: 100,
+            seed: 0,
+            max_overhead: 1.0,
+            max_sent_per_received: 100,
+        }
+    }
+
+    pub fn run(&self) -> Report {
+        let trials = (0..self.trials as u64)
+            .map(|trial| self.run_trial(self.seed.wrapping_add(trial)))
+            .collect();
+        Report {
+            num_blocks: self.num_blocks,
+            trials,
+        }
+    }
+
+    pub fn run_trial(&self, seed: u64) -> TrialResult {
+        match &self.precode {
+            Some(precode) => self.run_trial_with_precode(seed, &**precode),
+            None => {
+                let precode = RandomPrecode {
+                    adapt_to_small_objects: self.adapt_to_small_objects,
+                    ..RandomPrecode::new(self.epsilon, self.q)
+                };
+                self.run_trial_with_precode(seed, &precode)
+            }
+        }
+    }
+
+    fn run_trial_with_precode<P: Precode + ?Sized>(&self, seed: u64, precode: &P) -> TrialResult {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(seed);
+        let mut data = vec![0; self.num_blocks * self.block_size];
+        rng.fill_bytes(&mut data);
+
+        let mut coder = OnlineCoder::with_precode(self.block_size, self.epsilon, precode);
+        let mut decoder = Decoder::with_precode(
+            self.num_blocks,
+            self.block_size,
+            seed,
+            self.epsilon,
+            precode,
+            0,
+        );
+        if self.adapt_to_small_objects {
+            coder = coder.with_small_object_adaptation();
+            decoder.degree_distribution = decoder
+                .degree_distribution
+                .adapted_to(decoder.num_augmented_blocks);
+        }
+        let mut channel = ChannelState::new(self.channel.clone());
+        let max_received = (self.num_blocks as f64 * (1.0 + self.max_overhead)).ceil() as usize;
+        let max_sent = max_received.saturating_mul(self.max_sent_per_received);
+
+        let mut result = TrialResult {
+            blocks_sent: 0,
+            blocks_received: 0,
+            completed: false,
+            decode_time: Duration::default(),
+        };
+        for (check_block_id, check_block) in coder.encode(data, seed) {
+            if result.blocks_sent >= max_sent {
+                break;
+            }
+            result.blocks_sent += 1;
+            if channel.is_erased(&mut rng) {
+                continue;
+            }
+            result.blocks_received += 1;
+            let start = Instant::now();
+            let decoded = decoder.decode_block(check_block_id, &check_block);
+            result.decode_time += start.elapsed();
+            if decoded.is_complete() {
+                result.completed = true;
+                break;
+            }
+            if result.blocks_received >= max_received {
+                break;
+            }
+        }
+        result
+    }
+}
+
+impl TrialResult {
+    /// Received blocks beyond `num_blocks`, as a fraction of `num_blocks`.
+    pub fn overhead(&self, num_blocks: usize) -> Option<f64> {
+        if self.completed {
+            Some(self.blocks_received as f64 / num_blocks as f64 - 1.0)
+        } else {
+            None
+        }
+    }
+}
+
+impl Report {
+    /// Overheads of the completed trials, sorted in increasing order.
+    pub fn overheads(&self) -> Vec<f64> {
+        let mut overheads: Vec<f64> = self
+            .trials
+            .iter()
+            .filter_map(|trial| trial.overhead(self.num_blocks))
+            .collect();
+        overheads.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        overheads
+    }
+
+    /// The overhead which a fraction `p` of all trials completed within, if there is one.
+    pub fn overhead_quantile(&self, p: f64) -> Option<f64> {
+        let overheads = self.overheads();
+        let rank = (p * self.trials.len() as f64).ceil().max(1.0) as usize;
+        overheads.get(rank - 1).copied()
+    }
+
+    pub fn mean_overhead(&self) -> Option<f64> {
+        let overheads = self.overheads();
+        if overheads.is_empty() {
+            None
+        } else {
+            Some(overheads.iter().sum::<f64>() / overheads.len() as f64)
+        }
+    }
+
+    /// Fraction of trials which did not complete with at most the given overhead.
+    pub fn failure_probability(&self, overhead: f64) -> f64 {
+        let failures = self
+            .trials
+            .iter()
+            .filter(|trial| match trial.overhead(self.num_blocks) {
+                Some(needed) => needed > overhead,
+                None => true,
+            })
+            .count();
+        failures as f64 / self.trials.len() as f64
+    }
+
+    pub fn mean_decode_time(&self) -> Duration {
+        let total: Duration = self.trials.iter().map(|trial| trial.decode_time).sum();
+        total / self.trials.len().max(1) as u32
+    }
+
+    pub fn max_decode_time(&self) -> Duration {
+        self.trials
+            .iter()
+            .map(|trial| trial.decode_time)
+            .max()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,109 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::mem;
+
+const NONE: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Node {
+    slot: u32,
+    next: u32,
+}
+
+/// One list of check block storage slots per augmented block, as singly linked lists sharing a
+/// single arena of nodes. Nodes of emptied lists are reused, so after the first few blocks a
+/// decoder stops allocating.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlotLists {
+    heads: Vec<u32>,
+    nodes: Vec<Node>,
+    // Head of the list of unused nodes, chained through `next`.
+    free: u32,
+    len: usize,
+}
+
+impl SlotLists {
+    /// Bytes used by each entry, once the arena has grown to hold it.
+    pub const ENTRY_SIZE: usize = mem::size_of::<Node>();
+
+    pub fn new(num_lists: usize) -> SlotLists {
+        Self::with_capacity(num_lists, 0)
+    }
+
+    pub fn with_capacity(num_lists: usize, capacity: usize) -> SlotLists {
+        SlotLists {
+            heads: vec![NONE; num_lists],
+            nodes: Vec::with_capacity(capacity),
+            free: NONE,
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, list: usize, slot: usize) {
+        let slot = u32::try_from(slot).expect("too many check block slots");
+        let node = Node {
+            slot,
+            next: self.heads[list],
+        };
+        let index = if self.free == NONE {
+            self.nodes.push(node);
+            u32::try_from(self.nodes.len() - 1).expect("too many buffered adjacencies")
+        } else {
+            let index = self.free;
+            self.free = self.nodes[index as usize].next;
+            self.nodes[index as usize] = node;
+            index
+        };
+        self.heads[list] = index;
+        self.len += 1;
+    }
+
+    /// Removes the most recently pushed slot of `list`.
+    pub fn pop(&mut self, list: usize) -> Option<usize> {
+        let index = self.heads[list];
+        if index == NONE {
+            return None;
+        }
+        let node = &mut self.nodes[index as usize];
+        let slot = node.slot;
+        self.heads[list] = node.next;
+        node.next = self.free;
+        self.free = index;
+        self.len -= 1;
+        Some(slot as usize)
+    }
+
+    /// Removes the most recently pushed entry for `slot` from `list`, returning false if there
+    /// was none. Linear in the length of the list.
+    pub fn remove(&mut self, list: usize, slot: usize) -> bool {
+        let mut previous = NONE;
+        let mut index = self.heads[list];
+        while index != NONE {
+            let node = self.nodes[index as usize];
+            if node.slot as usize == slot {
+                if previous == NONE {
+                    self.heads[list] = node.next;
+                } else {
+                    self.nodes[previous as usize].next = node.next;
+                }
+                self.nodes[index as usize].next = self.free;
+                self.free = index;
+                self.len -= 1;
+                return true;
+            }
+            previous = index;
+            index = node.next;
+        }
+        false
+    }
+
+    /// Total number of entries over all lists.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
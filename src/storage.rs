@@ -0,0 +1,192 @@
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use memmap2::MmapMut;
+#[cfg(feature = "std")]
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    path::Path,
+};
+
+/// Where a `Decoder` keeps its working set: the augmented (data and aux) blocks being decoded,
+/// and the payloads of buffered check blocks.
+pub trait Storage {
+    /// What the decoder hands back once every data block is decoded.
+    type Output;
+
+    fn augmented_data(&self) -> &[u8];
+
+    fn augmented_data_mut(&mut self) -> &mut [u8];
+
+    /// Space for the buffered check block in `slot`, or `None` if there is no room for it. The
+    /// decoder reuses slots once their check blocks have been used.
+    fn check_block_slot(&mut self, slot: usize, block_size: usize) -> Option<&mut [u8]>;
+
+    /// Called once all data blocks are decoded. They are the first `data_len` bytes of the
+    /// augmented data, the last `pad` of which are padding.
+    fn finish(&mut self, data_len: usize, pad: usize) -> Self::Output;
+}
+
+/// Keeps everything in RAM.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    augmented_data: Vec<u8>,
+    check_blocks: Vec<u8>,
+}
+
+impl MemoryStorage {
+    pub fn new(augmented_len: usize) -> MemoryStorage {
+        MemoryStorage {
+            augmented_data: vec![0; augmented_len],
+            check_blocks: Vec::new(),
+        }
+    }
+}
+
+impl Storage for MemoryStorage {
+    type Output = Vec<u8>;
+
+    fn augmented_data(&self) -> &[u8] {
+        &self.augmented_data
+    }
+
+    fn augmented_data_mut(&mut self) -> &mut [u8] {
+        &mut self.augmented_data
+    }
+
+    fn check_block_slot(&mut self, slot: usize, block_size: usize) -> Option<&mut [u8]> {
+        let end = (slot + 1) * block_size;
+        if self.check_blocks.len() < end {
+            self.check_blocks.resize(end, 0);
+        }
+        Some(&mut self.check_blocks[slot * block_size..end])
+    }
+
+    fn finish(&mut self, data_len: usize, _pad: usize) -> Vec<u8> {
+        self.check_blocks = Vec::new();
+        let mut decoded_data = core::mem::take(&mut self.augmented_data);
+        decoded_data.truncate(data_len);
+        decoded_data
+    }
+}
+
+/// Decodes into a caller-supplied buffer. The augmented blocks come first, and whatever is left
+/// over holds buffered check blocks; check blocks which don't fit are dropped.
+#[derive(Debug)]
+pub struct SliceStorage<'a> {
+    buf: &'a mut [u8],
+    augmented_len: usize,
+}
+
+impl<'a> SliceStorage<'a> {
+    pub fn new(buf: &'a mut [u8], augmented_len: usize) -> SliceStorage<'a> {
+        assert!(buf.len() >= augmented_len, "buffer too small");
+        for byte in buf[..augmented_len].iter_mut() {
+            *byte = 0;
+        }
+        SliceStorage { buf, augmented_len }
+    }
+}
+
+impl Storage for SliceStorage<'_> {
+    // The decoded data is left at the start of the buffer.
+    type Output = ();
+
+    fn augmented_data(&self) -> &[u8] {
+        &self.buf[..self.augmented_len]
+    }
+
+    fn augmented_data_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[..self.augmented_len]
+    }
+
+    fn check_block_slot(&mut self, slot: usize, block_size: usize) -> Option<&mut [u8]> {
+        let start = self.augmented_len + slot * block_size;
+        self.buf.get_mut(start..start + block_size)
+    }
+
+    fn finish(&mut self, _data_len: usize, _pad: usize) {}
+}
+
+/// Decodes into a memory-mapped file, so that objects larger than RAM can be recovered. Buffered
+/// check blocks are kept in the same file after the augmented blocks, growing it as needed. Once
+/// decoding completes the file is truncated to exactly the decoded data.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MmapStorage {
+    file: File,
+    map: Option<MmapMut>,
+    augmented_len: usize,
+}
+
+#[cfg(feature = "std")]
+impl MmapStorage {
+    pub fn create<P: AsRef<Path>>(path: P, augmented_len: usize) -> io::Result<MmapStorage> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        let mut storage = MmapStorage {
+            file,
+            map: None,
+            augmented_len,
+        };
+        storage.remap(augmented_len)?;
+        Ok(storage)
+    }
+
+    fn mapped_len(&self) -> usize {
+        self.map.as_ref().map_or(0, |map| map.len())
+    }
+
+    // Grows the file and maps all of it. On failure the old mapping is kept, so whatever was
+    // already stored stays reachable.
+    fn remap(&mut self, len: usize) -> io::Result<()> {
+        self.file.set_len(len as u64)?;
+        // Safety: the file was opened by this storage, which has exclusive use of it.
+        self.map = Some(unsafe { MmapMut::map_mut(&self.file)? });
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Storage for MmapStorage {
+    type Output = io::Result<()>;
+
+    fn augmented_data(&self) -> &[u8] {
+        match &self.map {
+            Some(map) => &map[..self.augmented_len],
+            None => &[],
+        }
+    }
+
+    fn augmented_data_mut(&mut self) -> &mut [u8] {
+        match &mut self.map {
+            Some(map) => &mut map[..self.augmented_len],
+            None => &mut [],
+        }
+    }
+
+    fn check_block_slot(&mut self, slot: usize, block_size: usize) -> Option<&mut [u8]> {
+        let start = self.augmented_len + slot * block_size;
+        if start + block_size > self.mapped_len() {
+            let len = usize::max(start + block_size, 2 * self.mapped_len());
+            // If the file can't grow, treat it like running out of room.
+            self.remap(len).ok()?;
+        }
+        self.map
+            .as_mut()
+            .map(|map| &mut map[start..start + block_size])
+    }
+
+    fn finish(&mut self, data_len: usize, pad: usize) -> io::Result<()> {
+        if let Some(map) = self.map.take() {
+            map.flush()?;
+        }
+        self.file.set_len((data_len - pad) as u64)?;
+        self.file.sync_all()
+    }
+}
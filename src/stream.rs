@@ -0,0 +1,238 @@
+use crate::adjacency::{get_adjacent_blocks, DegreeDistribution};
+use crate::decode::Decoder;
+use crate::precode::NoPrecode;
+use crate::storage::Storage;
+use crate::types::{BlockIndex, CheckBlockId, StreamId};
+use crate::util::xor_block;
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+
+// Source blocks in a stream are addressed by their absolute position, which unlike `BlockIndex`
+// is unbounded.
+pub type SourceIndex = u64;
+
+/// A check block covering the window `[window_start, window_start + window_len)` of source blocks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamBlock {
+    pub check_block_id: CheckBlockId,
+    pub window_start: SourceIndex,
+    pub window_len: usize,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamDelivery {
+    Block(SourceIndex, Vec<u8>),
+    // The block slid out of every window the sender will use before it could be decoded.
+    Lost(SourceIndex),
+}
+
+/// Sliding-window encoder for source data that is appended over time.
+#[derive(Clone, Debug)]
+pub struct StreamEncoder {
+    block_size: usize,
+    window_size: usize,
+    stream_id: StreamId,
+    degree_distribution: DegreeDistribution,
+    window: VecDeque<Vec<u8>>,
+    window_start: SourceIndex,
+    check_block_id: CheckBlockId,
+}
+
+impl StreamEncoder {
+    pub fn new(block_size: usize, window_size: usize, stream_id: StreamId) -> StreamEncoder {
+        Self::with_parameters(block_size, window_size, stream_id, 0.01)
+    }
+
+    pub fn with_parameters(
+        block_size: usize,
+        window_size: usize,
+        stream_id: StreamId,
+        epsilon: f64,
+    ) -> StreamEncoder {
+        assert!(window_size > 0);
+        StreamEncoder {
+            block_size,
+            window_size,
+            stream_id,
+            degree_distribution: DegreeDistribution::for_blocks(epsilon, window_size),
+            window: VecDeque::with_capacity(window_size),
+            window_start: 0,
+            check_block_id: 0,
+        }
+    }
+
+    /// Appends a source block, zero-padding it if it is short. Returns its index in the stream.
+    pub fn push_block(&mut self, block: &[u8]) -> SourceIndex {
+        assert!(block.len() <= self.block_size);
+        let mut block = block.to_vec();
+        block.resize(self.block_size, 0);
+        if self.window.len() == self.window_size {
+            self.window.pop_front();
+            self.window_start += 1;
+        }
+        self.window.push_back(block);
+        self.window_start + self.window.len() as SourceIndex - 1
+    }
+
+    pub fn num_source_blocks(&self) -> SourceIndex {
+        self.window_start + self.window.len() as SourceIndex
+    }
+
+    /// Produces a check block over the current window, or `None` if nothing has been pushed yet.
+    pub fn next_block(&mut self) -> Option<StreamBlock> {
+        if self.window.is_empty() {
+            return None;
+        }
+        let mut data = vec![0; self.block_size];
+        for offset in get_adjacent_blocks(
+            self.check_block_id,
+            self.stream_id,
+            &self.degree_distribution,
+            self.window.len(),
+        ) {
+            xor_block(&mut data, &self.window[offset], self.block_size);
+        }
+
+        self.check_block_id += 1;
+        Some(StreamBlock {
+            check_block_id: self.check_block_id - 1,
+            window_start: self.window_start,
+            window_len: self.window.len(),
+            data,
+        })
+    }
+}
+
+/// Decoder for `StreamEncoder` output which hands back source blocks in order. The peeling is
+/// done by a `Decoder` over a ring of `window_size + 1` block positions, source block `i` taking
+/// position `i % (window_size + 1)`. Check blocks only cover the latest window, so the spare
+/// position is never used, and the decoder never considers the stream complete.
+#[derive(Clone, Debug)]
+pub struct StreamDecoder {
+    stream_id: StreamId,
+    window_size: usize,
+    degree_distribution: DegreeDistribution,
+    decoder: Decoder,
+    next_delivery: SourceIndex,
+    // No check block will reference source blocks before this index again.
+    window_floor: SourceIndex,
+    // Reused for every check block.
+    adjacent_blocks: Vec<BlockIndex>,
+}
+
+impl StreamDecoder {
+    pub fn new(block_size: usize, window_size: usize, stream_id: StreamId) -> StreamDecoder {
+        Self::with_parameters(block_size, window_size, stream_id, 0.01)
+    }
+
+    pub fn with_parameters(
+        block_size: usize,
+        window_size: usize,
+        stream_id: StreamId,
+        epsilon: f64,
+    ) -> StreamDecoder {
+        assert!(window_size > 0);
+        StreamDecoder {
+            stream_id,
+            window_size,
+            degree_distribution: DegreeDistribution::for_blocks(epsilon, window_size),
+            decoder: Decoder::with_precode(
+                window_size + 1,
+                block_size,
+                stream_id,
+                epsilon,
+                &NoPrecode,
+                0,
+            ),
+            next_delivery: 0,
+            window_floor: 0,
+            adjacent_blocks: Vec::new(),
+        }
+    }
+
+    /// Index of the next source block that will be delivered.
+    pub fn next_delivery(&self) -> SourceIndex {
+        self.next_delivery
+    }
+
+    /// Feeds a check block and returns the source blocks which became deliverable, in order.
+    /// Duplicates are ignored.
+    pub fn decode_block(&mut self, block: &StreamBlock) -> Vec<StreamDelivery> {
+        let mut delivered = Vec::new();
+        if block.window_start < self.window_floor {
+            // Stale: it may reference blocks whose data has already been released.
+            return delivered;
+        }
+        if block.window_len == 0 || block.window_len > self.window_size {
+            return delivered;
+        }
+        if block.window_start > self.window_floor {
+            self.advance_floor(block.window_start, &mut delivered);
+        }
+
+        let mut adjacent_blocks = mem::take(&mut self.adjacent_blocks);
+        adjacent_blocks.clear();
+        for offset in get_adjacent_blocks(
+            block.check_block_id,
+            self.stream_id,
+            &self.degree_distribution,
+            block.window_len,
+        ) {
+            adjacent_blocks.push(self.position(block.window_start + offset as SourceIndex));
+        }
+        self.decoder.decode_block_with_adjacency(
+            self.stream_id,
+            block.check_block_id,
+            &block.data,
+            &adjacent_blocks,
+        );
+        self.adjacent_blocks = adjacent_blocks;
+
+        // The position after the window is never decoded, so this stops there at the latest.
+        while let Some(data) = self.decoded_block(self.next_delivery) {
+            delivered.push(StreamDelivery::Block(self.next_delivery, data.to_vec()));
+            self.next_delivery += 1;
+        }
+        delivered
+    }
+
+    fn position(&self, index: SourceIndex) -> BlockIndex {
+        (index % (self.window_size as SourceIndex + 1)) as BlockIndex
+    }
+
+    // The data of a source block in the current window, if it has been decoded.
+    fn decoded_block(&self, index: SourceIndex) -> Option<&[u8]> {
+        let position = self.position(index);
+        if self.decoder.blocks_decoded[position] {
+            let block_size = self.decoder.block_size;
+            Some(&self.decoder.storage.augmented_data()[position * block_size..][..block_size])
+        } else {
+            None
+        }
+    }
+
+    fn advance_floor(&mut self, floor: SourceIndex, delivered: &mut Vec<StreamDelivery>) {
+        // Only source blocks in the old window can have been decoded.
+        let old_window_end = floor.min(self.window_floor + self.window_size as SourceIndex);
+        for index in self.next_delivery.max(self.window_floor)..floor {
+            let data = if index < old_window_end {
+                self.decoded_block(index)
+            } else {
+                None
+            };
+            delivered.push(match data {
+                Some(data) => StreamDelivery::Block(index, data.to_vec()),
+                None => StreamDelivery::Lost(index),
+            });
+            self.next_delivery = index + 1;
+        }
+        let released: Vec<BlockIndex> = (self.window_floor..old_window_end)
+            .map(|index| self.position(index))
+            .collect();
+        self.decoder.forget_blocks(&released);
+        self.window_floor = floor;
+    }
+}
@@ -1,4 +1,98 @@
+use crate::adjacency::{DegreeDistribution, DegreeSampler};
+use crate::rng::{RngAlgorithm, SecretKey};
+
 // TODO: the IDs should be u128
 pub type StreamId = u64;
+// Identifies the data being sent, and keys the outer code. Several streams can carry the same
+// object.
+pub type ObjectId = u64;
 pub type CheckBlockId = u64;
 pub type BlockIndex = usize;
+
+/// Everything besides the block size which the encoder and decoder of an object must agree on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CodeParameters {
+    pub epsilon: f64,
+    pub q: usize,
+    pub rng_algorithm: RngAlgorithm,
+    pub degree_sampler: DegreeSampler,
+    // Fits the degree distribution and the outer code to objects smaller than the distribution's
+    // maximum degree, see `DegreeDistribution::adapted_to`.
+    pub adapt_to_small_objects: bool,
+}
+
+impl Default for CodeParameters {
+    fn default() -> CodeParameters {
+        CodeParameters {
+            epsilon: 0.01,
+            q: 3,
+            rng_algorithm: RngAlgorithm::default(),
+            degree_sampler: DegreeSampler::default(),
+            adapt_to_small_objects: false,
+        }
+    }
+}
+
+impl CodeParameters {
+    pub const ENCODED_LEN: usize = 14;
+
+    /// The degree distribution before any adaptation to an object, for sharing between the
+    /// decoders of many objects with `Decoder::with_degree_distribution`.
+    pub fn degree_distribution(&self) -> DegreeDistribution {
+        DegreeDistribution::new(self.epsilon).with_sampler(self.degree_sampler)
+    }
+
+    /// `[version, rng_algorithm, q: u32, epsilon: f64]`, big-endian, for sending to peers. Secret
+    /// keys are left out. Version 1 draws degrees with `DegreeSampler::InverseCdf`, and version 2
+    /// with `DegreeSampler::Alias`. Versions 3 and 4 are the same, but adapted to small objects.
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0; Self::ENCODED_LEN];
+        bytes[0] = match (self.degree_sampler, self.adapt_to_small_objects) {
+            (DegreeSampler::InverseCdf, false) => 1,
+            (DegreeSampler::Alias, false) => 2,
+            (DegreeSampler::InverseCdf, true) => 3,
+            (DegreeSampler::Alias, true) => 4,
+        };
+        bytes[1] = self.rng_algorithm.id();
+        bytes[2..6].copy_from_slice(&(self.q as u32).to_be_bytes());
+        bytes[6..14].copy_from_slice(&self.epsilon.to_bits().to_be_bytes());
+        bytes
+    }
+
+    /// Returns `None` for unknown versions and algorithms, and for keyed ones, which need
+    /// `from_bytes_with_key`.
+    pub fn from_bytes(bytes: &[u8]) -> Option<CodeParameters> {
+        Self::parse(bytes, RngAlgorithm::from_id)
+    }
+
+    pub fn from_bytes_with_key(bytes: &[u8], key: SecretKey) -> Option<CodeParameters> {
+        Self::parse(bytes, |id| RngAlgorithm::from_keyed_id(id, key))
+    }
+
+    fn parse<F: FnOnce(u8) -> Option<RngAlgorithm>>(
+        bytes: &[u8],
+        rng_algorithm: F,
+    ) -> Option<CodeParameters> {
+        if bytes.len() != Self::ENCODED_LEN {
+            return None;
+        }
+        let (degree_sampler, adapt_to_small_objects) = match bytes[0] {
+            1 => (DegreeSampler::InverseCdf, false),
+            2 => (DegreeSampler::Alias, false),
+            3 => (DegreeSampler::InverseCdf, true),
+            4 => (DegreeSampler::Alias, true),
+            _ => return None,
+        };
+        let mut q = [0; 4];
+        q.copy_from_slice(&bytes[2..6]);
+        let mut epsilon = [0; 8];
+        epsilon.copy_from_slice(&bytes[6..14]);
+        Some(CodeParameters {
+            epsilon: f64::from_bits(u64::from_be_bytes(epsilon)),
+            q: u32::from_be_bytes(q) as usize,
+            rng_algorithm: rng_algorithm(bytes[1])?,
+            degree_sampler,
+            adapt_to_small_objects,
+        })
+    }
+}
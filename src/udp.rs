@@ -0,0 +1,302 @@
+//! File transfer over UDP, unicast or multicast. The sender streams check blocks at a fixed
+//! rate for as long as it is asked to; receivers join at any time and finish once they have
+//! enough of them, without any acknowledgements.
+//!
+//! Every packet carries a header describing the transfer, so receivers only need to know which
+//! stream to listen for: `stream_id: u64, len: u64, block_size: u32, check_block_id: u64`,
+//! big-endian, followed by the check block.
+
+use crate::decode::{BlockOutcome, Decoder};
+use crate::types::{CheckBlockId, StreamId};
+use crate::{decode_block, new_decoder, new_encoder, next_block, Encoder};
+use std::fs;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const HEADER_LEN: usize = 28;
+// Largest UDP payload.
+const MAX_PACKET_LEN: usize = 65536;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PacketHeader {
+    pub stream_id: StreamId,
+    pub len: u64,
+    pub block_size: u32,
+    pub check_block_id: CheckBlockId,
+}
+
+impl PacketHeader {
+    pub fn write(&self, buf: &mut [u8]) {
+        buf[0..8].copy_from_slice(&self.stream_id.to_be_bytes());
+        buf[8..16].copy_from_slice(&self.len.to_be_bytes());
+        buf[16..20].copy_from_slice(&self.block_size.to_be_bytes());
+        buf[20..28].copy_from_slice(&self.check_block_id.to_be_bytes());
+    }
+
+    pub fn read(buf: &[u8]) -> Option<PacketHeader> {
+        if buf.len() < HEADER_LEN {
+            return None;
+        }
+        let u64_at = |i: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&buf[i..i + 8]);
+            u64::from_be_bytes(bytes)
+        };
+        let mut block_size = [0; 4];
+        block_size.copy_from_slice(&buf[16..20]);
+        Some(PacketHeader {
+            stream_id: u64_at(0),
+            len: u64_at(8),
+            block_size: u32::from_be_bytes(block_size),
+            check_block_id: u64_at(20),
+        })
+    }
+}
+
+pub struct UdpSender {
+    socket: UdpSocket,
+    target: SocketAddr,
+    encoder: Encoder,
+    stream_id: StreamId,
+    len: u64,
+    block_size: u32,
+    // Time between packets, or `None` to send as fast as possible.
+    interval: Option<Duration>,
+    packet: Vec<u8>,
+    pub packets_sent: u64,
+}
+
+impl UdpSender {
+    /// `packets_per_second` of zero sends as fast as the socket allows. Multicast targets are
+    /// sent with a TTL of 1, so they stay on the local network unless `set_multicast_ttl` is
+    /// used.
+    pub fn new(
+        data: Vec<u8>,
+        block_size: u32,
+        stream_id: StreamId,
+        target: SocketAddr,
+        packets_per_second: f64,
+    ) -> io::Result<UdpSender> {
+        let bind_address: SocketAddr = if target.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(bind_address)?;
+        let len = data.len() as u64;
+        Ok(UdpSender {
+            socket,
+            target,
+            encoder: new_encoder(data, block_size as usize, stream_id),
+            stream_id,
+            len,
+            block_size,
+            interval: if packets_per_second > 0.0 {
+                Some(Duration::from_secs_f64(1.0 / packets_per_second))
+            } else {
+                None
+            },
+            packet: vec![0; HEADER_LEN + block_size as usize],
+            packets_sent: 0,
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        block_size: u32,
+        stream_id: StreamId,
+        target: SocketAddr,
+        packets_per_second: f64,
+    ) -> io::Result<UdpSender> {
+        UdpSender::new(
+            fs::read(path)?,
+            block_size,
+            stream_id,
+            target,
+            packets_per_second,
+        )
+    }
+
+    pub fn set_multicast_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.socket.set_multicast_ttl_v4(ttl)
+    }
+
+    /// Sends the next check block immediately.
+    pub fn send_block(&mut self) -> io::Result<()> {
+        let (check_block_id, check_block) = next_block(&mut self.encoder)
+            .ok_or_else(|| io::Error::other("ran out of check block ids"))?;
+        PacketHeader {
+            stream_id: self.stream_id,
+            len: self.len,
+            block_size: self.block_size,
+            check_block_id,
+        }
+        .write(&mut self.packet);
+        self.packet[HEADER_LEN..].copy_from_slice(&check_block);
+        self.socket.send_to(&self.packet, self.target)?;
+        self.packets_sent += 1;
+        Ok(())
+    }
+
+    /// Sends check blocks at the configured rate until `stop` returns true. It is checked before
+    /// every packet.
+    pub fn send_until<F: FnMut() -> bool>(&mut self, mut stop: F) -> io::Result<()> {
+        // Pace from the start, so that slow sends don't lower the rate.
+        let mut due = Instant::now();
+        while !stop() {
+            if let Some(interval) = self.interval {
+                let now = Instant::now();
+                if due > now {
+                    thread::sleep(due - now);
+                }
+                due += interval;
+            }
+            self.send_block()?;
+        }
+        Ok(())
+    }
+
+    pub fn send_blocks(&mut self, num_blocks: u64) -> io::Result<()> {
+        let mut remaining = num_blocks;
+        self.send_until(|| {
+            let done = remaining == 0;
+            remaining = remaining.saturating_sub(1);
+            done
+        })
+    }
+}
+
+pub struct UdpReceiver {
+    socket: UdpSocket,
+    stream_id: StreamId,
+    // Transfers claiming to be longer are ignored, as the decoder is sized from the header.
+    max_len: u64,
+    // Created from the first packet of the stream, whose length and block size the receiver then
+    // sticks to.
+    decoder: Option<(PacketHeader, Decoder)>,
+    pub packets_received: u64,
+    // Packets of other streams, or which aren't check blocks at all.
+    pub packets_ignored: u64,
+    // Duplicates, and packets whose blocks were all decoded already.
+    pub packets_redundant: u64,
+}
+
+impl UdpReceiver {
+    /// Binds to `address`, joining it as a group on every interface if it is an IPv4 multicast
+    /// address, to receive the stream `stream_id` of at most `max_len` bytes.
+    pub fn bind(address: SocketAddr, stream_id: StreamId, max_len: u64) -> io::Result<UdpReceiver> {
+        let socket = match address {
+            SocketAddr::V4(v4) if v4.ip().is_multicast() => {
+                let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, v4.port()))?;
+                socket.join_multicast_v4(v4.ip(), &Ipv4Addr::UNSPECIFIED)?;
+                socket
+            }
+            _ => UdpSocket::bind(address)?,
+        };
+        Ok(UdpReceiver {
+            socket,
+            stream_id,
+            max_len,
+            decoder: None,
+            packets_received: 0,
+            packets_ignored: 0,
+            packets_redundant: 0,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Receives packets until the data is decoded. Fails with `TimedOut` if that takes longer
+    /// than `timeout`.
+    pub fn receive(&mut self, timeout: Option<Duration>) -> io::Result<Vec<u8>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut packet = vec![0; MAX_PACKET_LEN];
+        loop {
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "transfer incomplete",
+                    ));
+                }
+                self.socket.set_read_timeout(Some(deadline - now))?;
+            }
+            let len = match self.socket.recv(&mut packet) {
+                Ok(len) => len,
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    continue
+                }
+                Err(e) => return Err(e),
+            };
+            if let Some(data) = self.handle_packet(&packet[..len]) {
+                return Ok(data);
+            }
+        }
+    }
+
+    pub fn receive_to_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        timeout: Option<Duration>,
+    ) -> io::Result<()> {
+        let data = self.receive(timeout)?;
+        fs::write(path, data)
+    }
+
+    fn handle_packet(&mut self, packet: &[u8]) -> Option<Vec<u8>> {
+        self.packets_received += 1;
+        // The block size is bounded by the packet length.
+        let header = match PacketHeader::read(packet) {
+            Some(header)
+                if header.stream_id == self.stream_id
+                    && header.len <= self.max_len
+                    && header.block_size > 0
+                    && packet.len() == HEADER_LEN + header.block_size as usize =>
+            {
+                header
+            }
+            _ => {
+                self.packets_ignored += 1;
+                return None;
+            }
+        };
+        let (first_header, decoder) = self.decoder.get_or_insert_with(|| {
+            let decoder = new_decoder(
+                header.len as usize,
+                header.block_size as usize,
+                header.stream_id,
+            );
+            (header, decoder)
+        });
+        if (header.stream_id, header.len, header.block_size)
+            != (
+                first_header.stream_id,
+                first_header.len,
+                first_header.block_size,
+            )
+        {
+            self.packets_ignored += 1;
+            return None;
+        }
+        match decode_block(
+            (header.check_block_id, packet[HEADER_LEN..].to_vec()),
+            decoder,
+        ) {
+            BlockOutcome::Completed(data) => Some(data),
+            BlockOutcome::Redundant => {
+                self.packets_redundant += 1;
+                None
+            }
+            _ => None,
+        }
+    }
+}
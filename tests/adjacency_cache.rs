@@ -0,0 +1,102 @@
+extern crate online_codes;
+
+use online_codes::adjacency_cache::AdjacencyCache;
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+
+const NUM_BLOCKS: usize = 2000;
+const BLOCK_SIZE: usize = 4;
+
+fn test_data() -> Vec<u8> {
+    (0..NUM_BLOCKS * BLOCK_SIZE)
+        .map(|i| (i * 11 + 2) as u8)
+        .collect()
+}
+
+fn received_blocks() -> Vec<(u64, Vec<u8>)> {
+    OnlineCoder::new(BLOCK_SIZE)
+        .encode(test_data(), 4)
+        .filter(|(check_block_id, _)| check_block_id % 4 != 1)
+        .take(10 * NUM_BLOCKS)
+        .collect()
+}
+
+// Feeds blocks until decoding completes, calling `between` before each one.
+fn decode<F: FnMut(&mut Decoder, usize)>(mut decoder: Decoder, mut between: F) -> Decoder {
+    for (i, (check_block_id, check_block)) in received_blocks().into_iter().enumerate() {
+        between(&mut decoder, i);
+        if let Some(decoded) = decoder
+            .decode_block(check_block_id, &check_block)
+            .complete()
+        {
+            assert_eq!(decoded, test_data());
+            return decoder;
+        }
+    }
+    panic!("decoding didn't complete");
+}
+
+#[test]
+fn test_caching_doesnt_change_decoding() {
+    let mut cached = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 4, 0);
+    let mut regenerated = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 4, 0);
+    assert!(cached.cache_adjacencies);
+    regenerated.cache_adjacencies = false;
+    for (check_block_id, check_block) in received_blocks() {
+        let decoded = cached.decode_block(check_block_id, &check_block);
+        assert_eq!(
+            regenerated.decode_block(check_block_id, &check_block),
+            decoded
+        );
+        assert_eq!(regenerated.blocks_decoded, cached.blocks_decoded);
+        assert_eq!(regenerated.check_block_slots, cached.check_block_slots);
+        assert_eq!(regenerated.adjacency_cache.len(), 0);
+        assert_eq!(
+            cached.buffered_bytes(),
+            regenerated.buffered_bytes() + 4 * cached.adjacency_cache.len()
+        );
+        if decoded.is_complete() {
+            return;
+        }
+    }
+    panic!("decoding didn't complete");
+}
+
+#[test]
+fn test_switching_caching_while_decoding() {
+    decode(Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 4, 0), |decoder, i| {
+        decoder.cache_adjacencies = i % 500 < 250;
+    });
+}
+
+#[test]
+fn test_cached_adjacencies_are_released() {
+    let decoder = decode(Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 4, 0), |_, _| {});
+    assert!(decoder.adjacency_cache.is_empty());
+}
+
+#[test]
+fn test_adjacency_cache() {
+    let mut cache = AdjacencyCache::new();
+    let mut out = Vec::new();
+    assert!(!cache.take(0, &mut out));
+    cache.insert(2, &[1, 5, 9]);
+    cache.insert(0, &[3, 4]);
+    assert_eq!(cache.len(), 5);
+    assert!(cache.take(2, &mut out));
+    assert_eq!(out, [1, 5, 9]);
+    assert!(!cache.take(2, &mut out));
+    cache.remove(0);
+    assert!(cache.is_empty());
+
+    // Reusing slots many times over keeps the other lists intact.
+    cache.insert(1, &[7, 8]);
+    for i in 0..1000 {
+        cache.insert(0, &[i, i + 1, i + 2]);
+    }
+    assert_eq!(cache.len(), 5);
+    assert!(cache.take(0, &mut out));
+    assert_eq!(out, [999, 1000, 1001]);
+    assert!(cache.take(1, &mut out));
+    assert_eq!(out, [7, 8]);
+}
@@ -1,5 +1,6 @@
 extern crate online_codes;
 
+use online_codes::decode::Incomplete;
 use online_codes::types::StreamId;
 use online_codes::{decode_block, new_decoder, new_encoder, next_block};
 use proptest::prelude::*;
@@ -62,20 +63,18 @@ fn check_encode_decode(buf: Vec<u8>) -> Option<Vec<u8>> {
     let buf_len = buf.len();
     let mut encoder = new_encoder(buf.clone(), 3, 0);
     let mut decoder = new_decoder(buf_len, 3, 0);
+    decoder.policy.max_received_blocks = Some(MAX_RECEIVED_BLOCKS);
 
-    // TODO: Should we put a limit or loop infinitely?
-    loop {
-        match next_block(&mut encoder) {
-            Some(block) => {
-                println!("block: {:?}", block);
-                match decode_block(block, &mut decoder) {
-                    None => continue,
-                    Some(res) => return Some(res),
-                }
-            }
-            None => continue,
+    while let Some(block) = next_block(&mut encoder) {
+        println!("block: {:?}", block);
+        if let Some(res) = decode_block(block, &mut decoder).complete() {
+            return Some(res);
+        }
+        if let Some(reason) = decoder.check_policy() {
+            gave_up(decoder.give_up(reason));
         }
     }
+    None
 }
 
 fn check_encode_decode_with_loss(buf: Vec<u8>, loss: f64) -> Option<(Vec<u8>, StreamId, u32)> {
@@ -88,26 +87,33 @@ fn check_encode_decode_with_loss(buf: Vec<u8>, loss: f64) -> Option<(Vec<u8>, St
     let buf_len = buf.len();
     let mut encoder = new_encoder(buf.clone(), 4, 0);
     let mut decoder = new_decoder(buf_len, 4, 0);
+    decoder.policy.max_received_blocks = Some(MAX_RECEIVED_BLOCKS);
 
-    // TODO: Should we put a limit or loop infinitely?
-    loop {
+    while let Some(block) = next_block(&mut encoder) {
         total_counter += 1;
-        match next_block(&mut encoder) {
-            Some(block) => {
-                let rand: f64 = loss_rng.gen::<f64>();
-                println!("block: {:?}", block);
-                if rand > loss {
-                    match decode_block(block, &mut decoder) {
-                        None => continue,
-                        Some(res) => return Some((res, loss_counter, total_counter)),
-                    }
-                } else {
-                    // Ignore this block and do nothing
-                    loss_counter += 1
-                }
+        let rand: f64 = loss_rng.gen::<f64>();
+        println!("block: {:?}", block);
+        if rand > loss {
+            if let Some(res) = decode_block(block, &mut decoder).complete() {
+                return Some((res, loss_counter, total_counter));
             }
-
-            None => continue,
+            if let Some(reason) = decoder.check_policy() {
+                gave_up(decoder.give_up(reason));
+            }
+        } else {
+            // Ignore this block and do nothing
+            loss_counter += 1
         }
     }
+    None
+}
+
+// A handful of blocks is normally enough; this many means decoding is stuck.
+const MAX_RECEIVED_BLOCKS: u64 = 10_000;
+
+fn gave_up(incomplete: Incomplete) -> ! {
+    panic!(
+        "gave up ({:?}) with only {:?} recovered",
+        incomplete.reason, incomplete.recovered_ranges
+    );
 }
@@ -0,0 +1,152 @@
+extern crate online_codes;
+
+use online_codes::decode::{BlockOutcome, Decoder, DecoderCapacity, RejectReason};
+use online_codes::encode::OnlineCoder;
+use online_codes::{decode_block, new_decoder, new_encoder, next_block};
+
+const NUM_BLOCKS: usize = 500;
+const BLOCK_SIZE: usize = 8;
+
+fn test_data() -> Vec<u8> {
+    (0..NUM_BLOCKS * BLOCK_SIZE)
+        .map(|i| (i * 9 + 4) as u8)
+        .collect()
+}
+
+fn encoded_blocks() -> impl Iterator<Item = (u64, Vec<u8>)> {
+    OnlineCoder::new(BLOCK_SIZE).encode(test_data(), 7)
+}
+
+#[test]
+fn test_outcomes_until_complete() {
+    let mut decoder = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 7, 0);
+    let (mut buffered, mut progressed, mut redundant) = (0, 0, 0);
+    let mut newly_decoded_total = 0;
+    for (check_block_id, check_block) in encoded_blocks().take(10 * NUM_BLOCKS) {
+        let num_decoded_before = decoder.num_decoded_blocks;
+        match decoder.decode_block(check_block_id, &check_block) {
+            BlockOutcome::Buffered { degree } => {
+                assert!(degree >= 2);
+                assert_eq!(decoder.num_decoded_blocks, num_decoded_before);
+                buffered += 1;
+            }
+            BlockOutcome::Progressed { newly_decoded } => {
+                assert!(newly_decoded >= 1);
+                assert_eq!(
+                    decoder.num_decoded_blocks,
+                    num_decoded_before + newly_decoded
+                );
+                newly_decoded_total += newly_decoded;
+                progressed += 1;
+            }
+            BlockOutcome::Redundant => redundant += 1,
+            BlockOutcome::Completed(data) => {
+                assert_eq!(data, test_data());
+                assert!(buffered > 0 && progressed > 0 && redundant > 0);
+                assert!(newly_decoded_total < decoder.num_decoded_blocks);
+                assert_eq!(
+                    decoder.decode_block(check_block_id + 1, &check_block),
+                    BlockOutcome::AlreadyComplete
+                );
+                return;
+            }
+            outcome => panic!("unexpected {:?}", outcome),
+        }
+    }
+    panic!("decoding didn't complete");
+}
+
+#[test]
+fn test_duplicates_are_redundant() {
+    let mut decoder = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 7, 0);
+    let (check_block_id, check_block) = encoded_blocks().next().unwrap();
+    assert!(matches!(
+        decoder.decode_block(check_block_id, &check_block),
+        BlockOutcome::Buffered { .. }
+    ));
+    assert_eq!(
+        decoder.decode_block(check_block_id, &check_block),
+        BlockOutcome::Redundant
+    );
+    assert_eq!(decoder.num_duplicate_check_blocks, 1);
+}
+
+#[test]
+fn test_rejected_blocks() {
+    let mut decoder = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 7, 0);
+    let (check_block_id, check_block) = encoded_blocks().next().unwrap();
+    assert_eq!(
+        decoder.decode_block(check_block_id, &check_block[1..]),
+        BlockOutcome::Rejected(RejectReason::WrongBlockSize)
+    );
+    // Not taken as seen, so the right block is still used.
+    assert!(!decoder.has_seen(7, check_block_id));
+
+    let capacity = DecoderCapacity {
+        max_buffered_check_blocks: 0,
+        max_buffered_adjacencies: 0,
+    };
+    let mut decoder = Decoder::with_capacity(NUM_BLOCKS, BLOCK_SIZE, 7, 0.01, 3, 0, capacity);
+    assert_eq!(
+        decoder.decode_block(check_block_id, &check_block),
+        BlockOutcome::Rejected(RejectReason::BufferFull)
+    );
+    assert_eq!(decoder.num_dropped_check_blocks, 1);
+    assert!(!decoder.has_seen(7, check_block_id));
+
+    // Once there is room again, resending the dropped block gets it used.
+    decoder.capacity = Some(DecoderCapacity {
+        max_buffered_check_blocks: 1,
+        max_buffered_adjacencies: 1000,
+    });
+    assert!(matches!(
+        decoder.decode_block(check_block_id, &check_block),
+        BlockOutcome::Buffered { .. }
+    ));
+    assert!(decoder.has_seen(7, check_block_id));
+}
+
+#[test]
+fn test_empty_object_completes_once() {
+    let mut decoder = Decoder::new(0, BLOCK_SIZE, 7, 0);
+    let (check_block_id, check_block) = encoded_blocks().next().unwrap();
+    assert_eq!(
+        decoder.decode_block(check_block_id, &check_block),
+        BlockOutcome::Completed(Vec::new())
+    );
+    assert_eq!(
+        decoder.decode_block(check_block_id + 1, &check_block),
+        BlockOutcome::AlreadyComplete
+    );
+}
+
+#[test]
+fn test_queued_in_incremental_mode() {
+    let mut decoder = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 7, 0);
+    decoder.incremental = true;
+    let (check_block_id, check_block) = encoded_blocks().next().unwrap();
+    assert_eq!(
+        decoder.decode_block(check_block_id, &check_block),
+        BlockOutcome::Queued
+    );
+}
+
+#[test]
+fn test_crate_decode_block_strips_padding() {
+    let data: Vec<u8> = (0..1001u32).map(|i| i as u8).collect();
+    let mut encoder = new_encoder(data.clone(), 16, 3);
+    let mut decoder = new_decoder(data.len(), 16, 3);
+    for _ in 0..10_000 {
+        match decode_block(next_block(&mut encoder).unwrap(), &mut decoder) {
+            BlockOutcome::Completed(decoded) => {
+                assert_eq!(decoded, data);
+                return;
+            }
+            outcome => assert!(!matches!(
+                outcome,
+                BlockOutcome::AlreadyComplete | BlockOutcome::Rejected(_)
+            )),
+        }
+    }
+    panic!("decoding didn't complete");
+}
@@ -0,0 +1,135 @@
+extern crate online_codes;
+
+use online_codes::adjacency::DegreeSampler;
+use online_codes::decode::{Decoder, DecoderCapacity, Progress};
+use online_codes::encode::OnlineCoder;
+use online_codes::rng::RngAlgorithm;
+use online_codes::types::CodeParameters;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// Counts the allocations made by each thread, as tests run side by side.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+fn test_data() -> Vec<u8> {
+    (0..2000u32).map(|i| (i * 13 + 5) as u8).collect()
+}
+
+fn decode_with_capacity(capacity: DecoderCapacity) -> (Option<Vec<u8>>, Decoder) {
+    let data = test_data();
+    let mut decoder = Decoder::with_capacity(200, 10, 3, 0.01, 3, 0, capacity);
+    for (check_block_id, check_block) in OnlineCoder::new(10).encode(data, 3).take(10_000) {
+        let decoded = decoder
+            .decode_block(check_block_id, &check_block)
+            .complete();
+        assert!(decoder.num_buffered_check_blocks <= capacity.max_buffered_check_blocks);
+        assert!(decoder.adjacent_check_blocks.len() <= capacity.max_buffered_adjacencies);
+        if decoded.is_some() {
+            return (decoded, decoder);
+        }
+    }
+    (None, decoder)
+}
+
+#[test]
+fn test_ample_capacity() {
+    let (decoded, decoder) = decode_with_capacity(DecoderCapacity {
+        max_buffered_check_blocks: 1000,
+        max_buffered_adjacencies: 100_000,
+    });
+    assert_eq!(decoded, Some(test_data()));
+    assert_eq!(decoder.num_dropped_check_blocks, 0);
+}
+
+#[test]
+fn test_limited_capacity() {
+    let (decoded, decoder) = decode_with_capacity(DecoderCapacity {
+        max_buffered_check_blocks: 100,
+        max_buffered_adjacencies: 1000,
+    });
+    assert_eq!(decoded, Some(test_data()));
+    assert!(decoder.num_dropped_check_blocks > 0);
+}
+
+#[test]
+fn test_capacity_with_code_parameters() {
+    let parameters = CodeParameters {
+        rng_algorithm: RngAlgorithm::ChaCha8,
+        degree_sampler: DegreeSampler::Alias,
+        adapt_to_small_objects: true,
+        ..CodeParameters::default()
+    };
+    let capacity = DecoderCapacity {
+        max_buffered_check_blocks: 100,
+        max_buffered_adjacencies: 1000,
+    };
+    let mut decoder =
+        Decoder::with_code_parameters_and_capacity(200, 10, 3, &parameters, 0, capacity);
+    let decoded = OnlineCoder::with_code_parameters(10, &parameters)
+        .encode(test_data(), 3)
+        .take(10_000)
+        .find_map(|(check_block_id, check_block)| {
+            decoder
+                .decode_block(check_block_id, &check_block)
+                .complete()
+        });
+    assert_eq!(decoded, Some(test_data()));
+}
+
+#[test]
+fn test_decoding_within_capacity_does_not_allocate() {
+    let blocks: Vec<_> = OnlineCoder::new(10)
+        .encode(test_data(), 3)
+        .take(10_000)
+        .collect();
+    // Queued check blocks wait in the buffer too, so incremental decoding needs more room.
+    for (incremental, max_buffered_check_blocks) in [(false, 100), (true, 1000)] {
+        let capacity = DecoderCapacity {
+            max_buffered_check_blocks,
+            max_buffered_adjacencies: 10 * max_buffered_check_blocks,
+        };
+        let mut decoder = Decoder::with_capacity(200, 10, 3, 0.01, 3, 0, capacity);
+        decoder.incremental = incremental;
+        let mut completed = false;
+        for (check_block_id, check_block) in &blocks {
+            let allocations_before = allocations();
+            // Only handing back the decoded data may allocate.
+            completed = decoder
+                .decode_block(*check_block_id, check_block)
+                .is_complete()
+                || matches!(decoder.make_progress(usize::MAX), Progress::Complete(_));
+            if completed {
+                break;
+            }
+            assert_eq!(allocations(), allocations_before);
+        }
+        assert!(completed);
+    }
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "capi")]
+
+// Builds the crate as a static library, links `tests/capi/round_trip.c` against it using the
+// generated header, and runs the result.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn run(command: &mut Command) {
+    let status = command.status().unwrap();
+    assert!(status.success(), "{:?} failed: {}", command, status);
+}
+
+#[test]
+fn test_c_round_trip() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // A separate target directory, so as not to contend with the running `cargo test`.
+    let target_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("capi");
+    let header = target_dir.join("include").join("online_codes.h");
+    run(
+        Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+            .current_dir(manifest_dir)
+            .env("ONLINE_CODES_HEADER", &header)
+            .args([
+                "rustc",
+                "--lib",
+                "--features",
+                "capi",
+                "--crate-type",
+                "staticlib",
+            ])
+            .arg("--target-dir")
+            .arg(&target_dir),
+    );
+
+    let program = target_dir.join("round_trip");
+    run(Command::new(env::var("CC").unwrap_or_else(|_| "cc".into()))
+        .arg(manifest_dir.join("tests").join("capi").join("round_trip.c"))
+        .arg("-I")
+        .arg(header.parent().unwrap())
+        .arg(target_dir.join("debug").join("libonline_codes.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program));
+    run(&mut Command::new(program));
+}
@@ -0,0 +1,82 @@
+/* Encodes and decodes a buffer through the C ABI, dropping every third check block. */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "online_codes.h"
+
+#define LEN 10003
+#define BLOCK_SIZE 64
+#define STREAM_ID 42
+
+#define CHECK(cond)                                                                          \
+    do {                                                                                     \
+        if (!(cond)) {                                                                       \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond);         \
+            return 1;                                                                        \
+        }                                                                                    \
+    } while (0)
+
+int main(void) {
+    uint8_t *data = malloc(LEN);
+    uint8_t *decoded = malloc(LEN);
+    uint8_t block[BLOCK_SIZE];
+    uint64_t check_block_id;
+    size_t blocks_decoded, num_blocks;
+    size_t i;
+    OnlineCodesStatus status = ONLINE_CODES_STATUS_IN_PROGRESS;
+
+    CHECK(data != NULL && decoded != NULL);
+    for (i = 0; i < LEN; i++) {
+        data[i] = (uint8_t)(i * 7 + 1);
+    }
+
+    CHECK(online_codes_encoder_new(data, LEN, 0, STREAM_ID) == NULL);
+    OnlineCodesEncoder *encoder = online_codes_encoder_new(data, LEN, BLOCK_SIZE, STREAM_ID);
+    OnlineCodesDecoder *decoder = online_codes_decoder_new(LEN, BLOCK_SIZE, STREAM_ID);
+    CHECK(encoder != NULL && decoder != NULL);
+
+    CHECK(online_codes_encoder_next_block(encoder, &check_block_id, block, BLOCK_SIZE - 1) ==
+          ONLINE_CODES_STATUS_BUFFER_TOO_SMALL);
+    CHECK(online_codes_decoder_decode_block(NULL, 0, block, BLOCK_SIZE) ==
+          ONLINE_CODES_STATUS_NULL_POINTER);
+    CHECK(online_codes_decoder_decode_block(decoder, 0, block, BLOCK_SIZE - 1) ==
+          ONLINE_CODES_STATUS_INVALID_ARGUMENT);
+    CHECK(online_codes_decoder_result(decoder, decoded, LEN) == ONLINE_CODES_STATUS_IN_PROGRESS);
+
+    for (i = 0; i < 10000 && status == ONLINE_CODES_STATUS_IN_PROGRESS; i++) {
+        CHECK(online_codes_encoder_next_block(encoder, &check_block_id, block, BLOCK_SIZE) ==
+              ONLINE_CODES_STATUS_OK);
+        if (i % 3 == 2) {
+            continue;
+        }
+        status = online_codes_decoder_decode_block(decoder, check_block_id, block, BLOCK_SIZE);
+    }
+    CHECK(status == ONLINE_CODES_STATUS_OK);
+
+    CHECK(online_codes_decoder_progress(decoder, &blocks_decoded, &num_blocks) ==
+          ONLINE_CODES_STATUS_OK);
+    CHECK(num_blocks == (LEN + BLOCK_SIZE - 1) / BLOCK_SIZE);
+    CHECK(blocks_decoded == num_blocks);
+    CHECK(online_codes_decoder_result(decoder, decoded, LEN - 1) ==
+          ONLINE_CODES_STATUS_BUFFER_TOO_SMALL);
+    CHECK(online_codes_decoder_result(decoder, decoded, LEN) == ONLINE_CODES_STATUS_OK);
+    CHECK(memcmp(data, decoded, LEN) == 0);
+
+    online_codes_encoder_free(encoder);
+    online_codes_decoder_free(decoder);
+    online_codes_decoder_free(NULL);
+
+    /* An empty object is decoded by whichever check block arrives first. */
+    decoder = online_codes_decoder_new(0, BLOCK_SIZE, STREAM_ID);
+    CHECK(decoder != NULL);
+    CHECK(online_codes_decoder_progress(decoder, NULL, NULL) == ONLINE_CODES_STATUS_IN_PROGRESS);
+    CHECK(online_codes_decoder_decode_block(decoder, 0, block, BLOCK_SIZE) ==
+          ONLINE_CODES_STATUS_OK);
+    CHECK(online_codes_decoder_result(decoder, NULL, 0) == ONLINE_CODES_STATUS_OK);
+    online_codes_decoder_free(decoder);
+    free(data);
+    free(decoded);
+    return 0;
+}
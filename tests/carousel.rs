@@ -0,0 +1,164 @@
+extern crate online_codes;
+
+use online_codes::carousel::{Carousel, CarouselPacket, CarouselReceiver, ObjectAnnouncement};
+use std::collections::HashMap;
+
+fn object(stream_id: u64, len: usize) -> Vec<u8> {
+    (0..len)
+        .map(|i| (i as u64 * 7 + stream_id * 13) as u8)
+        .collect()
+}
+
+#[test]
+fn test_broadcast_many_objects() {
+    let mut carousel = Carousel::new(20);
+    let sizes = [(1, 5000), (2, 20_003), (3, 777)];
+    for &(stream_id, len) in &sizes {
+        assert!(carousel.add_object(stream_id, object(stream_id, len), 64, 1, 0));
+    }
+    assert!(!carousel.add_object(1, object(1, 10), 64, 1, 0));
+
+    let mut receiver = CarouselReceiver::new();
+    let mut decoded = HashMap::new();
+    for packet_number in 0..100_000 {
+        let packet = carousel.next_packet().unwrap();
+        // Every fifth packet, announcements included, is lost.
+        if packet_number % 5 == 4 {
+            continue;
+        }
+        let packet = CarouselPacket::from_bytes(&packet.to_bytes()).unwrap();
+        if let Some((stream_id, data)) = receiver.receive(packet) {
+            assert!(carousel.retire_object(stream_id));
+            decoded.insert(stream_id, data);
+        }
+        if carousel.num_objects() == 0 {
+            break;
+        }
+    }
+    assert!(carousel.next_packet().is_none());
+    for &(stream_id, len) in &sizes {
+        assert_eq!(decoded[&stream_id], object(stream_id, len));
+        assert!(receiver.is_complete(stream_id));
+    }
+    assert_eq!(receiver.in_progress().count(), 0);
+}
+
+#[test]
+fn test_late_receiver_learns_from_repeated_announcements() {
+    let mut carousel = Carousel::new(10);
+    carousel.add_object(5, object(5, 3000), 32, 1, 0);
+    // Miss the initial announcement and some blocks.
+    for _ in 0..5 {
+        carousel.next_packet();
+    }
+    let mut receiver = CarouselReceiver::new();
+    let mut result = None;
+    while result.is_none() {
+        result = receiver.receive(carousel.next_packet().unwrap());
+    }
+    assert_eq!(result, Some((5, object(5, 3000))));
+    assert!(receiver.num_unannounced_blocks > 0);
+}
+
+#[test]
+fn test_weights_share_blocks() {
+    let mut carousel = Carousel::new(1000);
+    carousel.add_object(1, object(1, 1000), 16, 1, 0);
+    carousel.add_object(2, object(2, 1000), 16, 3, 0);
+    for _ in 0..802 {
+        carousel.next_packet();
+    }
+    // Two announcements, then a 1:3 split.
+    assert_eq!(carousel.blocks_sent(1), Some(200));
+    assert_eq!(carousel.blocks_sent(2), Some(600));
+}
+
+#[test]
+fn test_priority_preempts_until_retired() {
+    let mut carousel = Carousel::new(1000);
+    carousel.add_object(1, object(1, 1000), 16, 1, 0);
+    for _ in 0..11 {
+        carousel.next_packet();
+    }
+    assert!(carousel.add_object(2, object(2, 1000), 16, 1, 1));
+    assert_eq!(
+        carousel.next_packet(),
+        Some(CarouselPacket::Announcement(ObjectAnnouncement {
+            stream_id: 2,
+            len: 1000,
+            block_size: 16,
+        }))
+    );
+    for _ in 0..50 {
+        match carousel.next_packet() {
+            Some(CarouselPacket::Block { stream_id, .. }) => assert_eq!(stream_id, 2),
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+    assert_eq!(carousel.blocks_sent(1), Some(10));
+
+    assert!(carousel.retire_object(2));
+    assert!(!carousel.retire_object(2));
+    carousel.next_packet();
+    assert_eq!(carousel.blocks_sent(1), Some(11));
+}
+
+#[test]
+fn test_malformed_packets_are_rejected() {
+    assert_eq!(CarouselPacket::from_bytes(&[]), None);
+    assert_eq!(CarouselPacket::from_bytes(&[0; 20]), None);
+    assert_eq!(CarouselPacket::from_bytes(&[2; 30]), None);
+}
+
+#[test]
+fn test_zero_block_size_or_weight_is_rejected() {
+    let mut carousel = Carousel::new(10);
+    assert!(!carousel.add_object(1, object(1, 100), 0, 1, 0));
+    assert!(!carousel.add_object(1, object(1, 100), 16, 0, 0));
+    assert_eq!(carousel.num_objects(), 0);
+}
+
+#[test]
+fn test_receiver_remembers_recent_objects() {
+    let mut receiver = CarouselReceiver::new();
+    for stream_id in 0..=CarouselReceiver::MAX_COMPLETED as u64 {
+        let announcement = ObjectAnnouncement {
+            stream_id,
+            len: 0,
+            block_size: 1,
+        };
+        receiver.receive(CarouselPacket::Announcement(announcement));
+        let block = CarouselPacket::Block {
+            stream_id,
+            check_block_id: 0,
+            data: vec![0],
+        };
+        assert_eq!(receiver.receive(block), Some((stream_id, Vec::new())));
+    }
+    assert!(!receiver.is_complete(0));
+    assert!(receiver.is_complete(1));
+    assert!(receiver.is_complete(CarouselReceiver::MAX_COMPLETED as u64));
+}
+
+#[test]
+fn test_empty_and_oversized_objects() {
+    let mut carousel = Carousel::new(10);
+    carousel.add_object(1, Vec::new(), 16, 1, 0);
+    let mut receiver = CarouselReceiver::with_max_len(1000);
+    assert_eq!(receiver.receive(carousel.next_packet().unwrap()), None);
+    let packet = carousel.next_packet().unwrap();
+    assert_eq!(receiver.receive(packet.clone()), Some((1, Vec::new())));
+    assert!(receiver.is_complete(1));
+    assert_eq!(receiver.receive(packet), None);
+
+    let oversized = ObjectAnnouncement {
+        stream_id: 2,
+        len: u64::MAX / 2,
+        block_size: 16,
+    };
+    assert_eq!(
+        receiver.receive(CarouselPacket::Announcement(oversized)),
+        None
+    );
+    assert_eq!(receiver.in_progress().count(), 0);
+}
@@ -0,0 +1,111 @@
+extern crate online_codes;
+
+use online_codes::adjacency::{DegreeDistribution, DegreeSampler};
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+use online_codes::rng::XoshiroRng;
+use online_codes::types::CodeParameters;
+
+fn histogram(degree_distribution: &DegreeDistribution, samples: u64) -> Vec<u64> {
+    let mut counts = vec![0; degree_distribution.max_degree() + 1];
+    let mut rng = XoshiroRng::new(5, 0);
+    for _ in 0..samples {
+        counts[degree_distribution.sample(&mut rng)] += 1;
+    }
+    counts
+}
+
+#[test]
+fn test_alias_sampler_has_the_same_distribution() {
+    for &(epsilon, num_blocks) in &[(0.01, 100_000), (0.1, 1000), (0.01, 30)] {
+        let inverse_cdf = DegreeDistribution::for_blocks(epsilon, num_blocks);
+        let alias = inverse_cdf.clone().with_sampler(DegreeSampler::Alias);
+        assert_eq!(alias.sampler(), DegreeSampler::Alias);
+        assert_eq!(alias.max_degree(), inverse_cdf.max_degree());
+        let samples = 200_000;
+        let expected = histogram(&inverse_cdf, samples);
+        let actual = histogram(&alias, samples);
+        assert_eq!(actual[0], 0);
+        for degree in 1..10 {
+            let expected = expected[degree] as f64 / samples as f64;
+            let actual = actual[degree] as f64 / samples as f64;
+            assert!(
+                (expected - actual).abs() < 0.01,
+                "degree {}: {} vs {}",
+                degree,
+                expected,
+                actual
+            );
+        }
+    }
+}
+
+#[test]
+fn test_adapting_keeps_the_sampler() {
+    let alias = DegreeDistribution::new(0.01).with_sampler(DegreeSampler::Alias);
+    assert_eq!(alias.adapted_to(10).sampler(), DegreeSampler::Alias);
+    // Large objects share the tables.
+    assert_eq!(alias.adapted_to(100_000), alias);
+    assert_eq!(
+        alias.with_sampler(DegreeSampler::InverseCdf).sampler(),
+        DegreeSampler::InverseCdf
+    );
+}
+
+#[test]
+fn test_round_trip_with_alias_sampler() {
+    let parameters = CodeParameters {
+        degree_sampler: DegreeSampler::Alias,
+        ..CodeParameters::default()
+    };
+    assert_eq!(parameters.to_bytes()[0], 2);
+    assert_eq!(
+        CodeParameters::from_bytes(&parameters.to_bytes()),
+        Some(parameters)
+    );
+    for &num_blocks in &[4, 3000] {
+        let data: Vec<u8> = (0..num_blocks * 8).map(|i| (i * 3 + 7) as u8).collect();
+        let coder = OnlineCoder::with_code_parameters(8, &parameters);
+        let mut decoder = Decoder::with_code_parameters(num_blocks, 8, 2, &parameters, 0);
+        let decoded = coder
+            .encode(data.clone(), 2)
+            .take(20 * num_blocks)
+            .find_map(|(check_block_id, check_block)| {
+                decoder
+                    .decode_block(check_block_id, &check_block)
+                    .complete()
+            });
+        assert_eq!(decoded, Some(data));
+    }
+}
+
+#[test]
+fn test_decoders_share_a_degree_distribution() {
+    let parameters = CodeParameters {
+        degree_sampler: DegreeSampler::Alias,
+        ..CodeParameters::default()
+    };
+    let degree_distribution = parameters.degree_distribution();
+    let coder = OnlineCoder::with_code_parameters(8, &parameters);
+    for object_id in 0..3 {
+        let data: Vec<u8> = (0..3000 * 8).map(|i| (i * 5 + object_id) as u8).collect();
+        let mut decoder = Decoder::with_degree_distribution(
+            3000,
+            8,
+            object_id as u64,
+            &parameters,
+            &degree_distribution,
+            0,
+        );
+        assert_eq!(decoder.degree_distribution, degree_distribution);
+        let decoded = coder
+            .encode(data.clone(), object_id as u64)
+            .take(20_000)
+            .find_map(|(check_block_id, check_block)| {
+                decoder
+                    .decode_block(check_block_id, &check_block)
+                    .complete()
+            });
+        assert_eq!(decoded, Some(data));
+    }
+}
@@ -0,0 +1,145 @@
+extern crate online_codes;
+
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+use online_codes::id_set::IdSet;
+use rand::seq::SliceRandom;
+use rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256StarStar;
+
+fn test_data() -> Vec<u8> {
+    (0..4000u32).map(|i| (i * 11 + 1) as u8).collect()
+}
+
+fn decode(blocks: &[(u64, Vec<u8>)]) -> (Option<Vec<u8>>, Decoder) {
+    let mut decoder = Decoder::new(500, 8, 9, 0);
+    for (check_block_id, check_block) in blocks {
+        if let Some(decoded) = decoder
+            .decode_block(*check_block_id, check_block)
+            .complete()
+        {
+            return (Some(decoded), decoder);
+        }
+    }
+    (None, decoder)
+}
+
+#[test]
+fn test_duplicated_stream() {
+    let blocks: Vec<_> = OnlineCoder::new(8)
+        .encode(test_data(), 9)
+        .take(2000)
+        .flat_map(|block| vec![block.clone(), block])
+        .collect();
+    let (decoded, decoder) = decode(&blocks);
+    assert_eq!(decoded, Some(test_data()));
+    assert!(decoder.num_duplicate_check_blocks > 0);
+    assert!(decoder.has_seen(9, 0));
+    assert!(!decoder.has_seen(10, 0));
+}
+
+#[test]
+fn test_reordered_stream_with_replays() {
+    let mut rng = Xoshiro256StarStar::seed_from_u64(33);
+    let mut blocks: Vec<_> = OnlineCoder::new(8)
+        .encode(test_data(), 9)
+        .take(1000)
+        .collect();
+    // Replay a random selection of earlier blocks, then shuffle everything.
+    let replays: Vec<_> = blocks.choose_multiple(&mut rng, 500).cloned().collect();
+    blocks.extend(replays);
+    blocks.shuffle(&mut rng);
+    let (decoded, decoder) = decode(&blocks);
+    assert_eq!(decoded, Some(test_data()));
+    assert!(decoder.num_duplicate_check_blocks > 0);
+}
+
+#[test]
+fn test_duplicates_leave_buffers_unchanged() {
+    let blocks: Vec<_> = OnlineCoder::new(8)
+        .encode(test_data(), 9)
+        .take(300)
+        .collect();
+    let mut decoder = Decoder::new(500, 8, 9, 0);
+    let mut duplicated_decoder = Decoder::new(500, 8, 9, 0);
+    for (check_block_id, check_block) in &blocks {
+        decoder.decode_block(*check_block_id, check_block);
+        duplicated_decoder.decode_block(*check_block_id, check_block);
+        duplicated_decoder.decode_block(*check_block_id, check_block);
+    }
+    assert_eq!(duplicated_decoder.num_duplicate_check_blocks, blocks.len());
+    assert_eq!(
+        duplicated_decoder.check_block_slots,
+        decoder.check_block_slots
+    );
+    assert_eq!(
+        duplicated_decoder.adjacent_check_blocks,
+        decoder.adjacent_check_blocks
+    );
+    assert_eq!(duplicated_decoder.blocks_decoded, decoder.blocks_decoded);
+}
+
+#[test]
+fn test_id_set() {
+    let mut ids = IdSet::with_window(100);
+    assert_eq!(ids.window(), 128);
+    for id in [5, 3, 4, 10, 7, 6, 0] {
+        assert!(ids.insert(id));
+        assert!(!ids.insert(id));
+    }
+    for id in [0, 3, 4, 5, 6, 7, 10] {
+        assert!(ids.contains(id));
+    }
+    for id in [1, 2, 8, 9, 11, 1000] {
+        assert!(!ids.contains(id));
+    }
+    ids.remove(4);
+    assert!(!ids.contains(4));
+
+    // Moving the window on forgets what it passes over, and older ids count as unseen.
+    assert!(ids.insert(130));
+    for id in [3, 5, 10, 130] {
+        assert!(ids.contains(id));
+    }
+    for id in [0, 1, 2, 4, 11, 128, 129] {
+        assert!(!ids.contains(id));
+    }
+    // Ids behind the window aren't remembered, so they don't mark the id sharing their bit.
+    assert!(ids.insert(1));
+    assert!(ids.insert(1));
+    assert!(!ids.contains(1));
+    assert!(!ids.contains(129));
+    assert!(ids.insert(u64::MAX));
+    assert!(!ids.contains(130));
+    assert!(!ids.contains(u64::MAX - 1));
+}
+
+#[test]
+fn test_id_set_stays_bounded() {
+    let mut ids = IdSet::new();
+    for id in (0..1_000_000).step_by(2) {
+        assert!(ids.insert(id));
+    }
+    assert_eq!(ids.window(), IdSet::DEFAULT_WINDOW);
+    assert!(ids.contains(999_998));
+    assert!(!ids.contains(999_997));
+    assert!(!ids.contains(0));
+}
+
+#[test]
+fn test_late_blocks_behind_the_window() {
+    let coder = OnlineCoder::new(8);
+    let late = 2 * IdSet::DEFAULT_WINDOW;
+    let newest = coder.encode(test_data(), 9).nth(late).unwrap();
+    // Blocks far behind the newest one are used, however often they arrive.
+    let mut blocks = vec![newest];
+    for block in coder.encode(test_data(), 9).take(1000) {
+        blocks.push(block.clone());
+        blocks.push(block);
+    }
+    let (decoded, decoder) = decode(&blocks);
+    assert_eq!(decoded, Some(test_data()));
+    assert_eq!(decoder.num_duplicate_check_blocks, 0);
+    assert!(decoder.has_seen(9, late as u64));
+    assert!(!decoder.has_seen(9, 0));
+}
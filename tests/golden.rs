@@ -0,0 +1,316 @@
+extern crate online_codes;
+
+// Golden vectors pinning down which blocks every check block is made of. These must never change:
+// a mismatch means blocks from existing senders would no longer decode.
+
+use online_codes::adjacency::{
+    get_adjacent_blocks_with_rng, get_aux_block_members, DegreeDistribution, DegreeSampler,
+};
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+use online_codes::precode::{Precode, RandomPrecode};
+use online_codes::rng::{chacha_block, AdjacencyRng, RngAlgorithm};
+use online_codes::types::CodeParameters;
+use std::fmt::Write;
+use std::fs;
+
+struct Vectors {
+    name: &'static str,
+    epsilon: f64,
+    q: usize,
+    num_blocks: usize,
+    block_size: usize,
+    stream_id: u64,
+    num_check_blocks: u64,
+    rng_algorithm: RngAlgorithm,
+    degree_sampler: DegreeSampler,
+    adapt_to_small_objects: bool,
+}
+
+const VECTORS: &[Vectors] = &[
+    Vectors {
+        name: "default",
+        epsilon: 0.01,
+        q: 3,
+        num_blocks: 4096,
+        block_size: 8,
+        stream_id: 0x0123_4567_89ab_cdef,
+        num_check_blocks: 32,
+        rng_algorithm: RngAlgorithm::Xoshiro256StarStar,
+        degree_sampler: DegreeSampler::InverseCdf,
+        adapt_to_small_objects: false,
+    },
+    Vectors {
+        name: "epsilon_0.1",
+        epsilon: 0.1,
+        q: 3,
+        num_blocks: 128,
+        block_size: 4,
+        stream_id: 7,
+        // Enough to decode the data.
+        num_check_blocks: 218,
+        rng_algorithm: RngAlgorithm::Xoshiro256StarStar,
+        degree_sampler: DegreeSampler::InverseCdf,
+        adapt_to_small_objects: false,
+    },
+    Vectors {
+        name: "chacha8",
+        epsilon: 0.01,
+        q: 3,
+        num_blocks: 4096,
+        block_size: 8,
+        stream_id: 0x0123_4567_89ab_cdef,
+        num_check_blocks: 32,
+        rng_algorithm: RngAlgorithm::ChaCha8,
+        degree_sampler: DegreeSampler::InverseCdf,
+        adapt_to_small_objects: false,
+    },
+    Vectors {
+        // Smaller than the maximum degree, which only changes the code when adapting to it.
+        name: "small_unadapted",
+        epsilon: 0.01,
+        q: 3,
+        num_blocks: 50,
+        block_size: 4,
+        stream_id: 9,
+        num_check_blocks: 64,
+        rng_algorithm: RngAlgorithm::Xoshiro256StarStar,
+        degree_sampler: DegreeSampler::InverseCdf,
+        adapt_to_small_objects: false,
+    },
+    Vectors {
+        // With the adapted degree distribution and no aux blocks.
+        name: "small",
+        epsilon: 0.01,
+        q: 3,
+        num_blocks: 50,
+        block_size: 4,
+        stream_id: 9,
+        num_check_blocks: 64,
+        rng_algorithm: RngAlgorithm::Xoshiro256StarStar,
+        degree_sampler: DegreeSampler::InverseCdf,
+        adapt_to_small_objects: true,
+    },
+    Vectors {
+        name: "alias",
+        epsilon: 0.01,
+        q: 3,
+        num_blocks: 4096,
+        block_size: 8,
+        stream_id: 0x0123_4567_89ab_cdef,
+        num_check_blocks: 32,
+        rng_algorithm: RngAlgorithm::Xoshiro256StarStar,
+        degree_sampler: DegreeSampler::Alias,
+        adapt_to_small_objects: false,
+    },
+];
+
+fn parameters(vectors: &Vectors) -> CodeParameters {
+    CodeParameters {
+        epsilon: vectors.epsilon,
+        q: vectors.q,
+        rng_algorithm: vectors.rng_algorithm,
+        degree_sampler: vectors.degree_sampler,
+        adapt_to_small_objects: vectors.adapt_to_small_objects,
+    }
+}
+
+fn data(vectors: &Vectors) -> Vec<u8> {
+    (0..vectors.num_blocks * vectors.block_size)
+        .map(|i| (i * 167 + 13) as u8)
+        .collect()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn render(vectors: &Vectors) -> String {
+    let mut out = String::new();
+    write!(
+        out,
+        "# epsilon={} q={} num_blocks={} block_size={} stream_id={}",
+        vectors.epsilon, vectors.q, vectors.num_blocks, vectors.block_size, vectors.stream_id
+    )
+    .unwrap();
+    if vectors.rng_algorithm != RngAlgorithm::default() {
+        write!(out, " rng={}", vectors.rng_algorithm.id()).unwrap();
+    }
+    if vectors.degree_sampler != DegreeSampler::default() {
+        write!(out, " sampler={:?}", vectors.degree_sampler).unwrap();
+    }
+    writeln!(out).unwrap();
+
+    for check_block_id in 0..2 {
+        let mut rng = vectors.rng_algorithm.rng(vectors.stream_id, check_block_id);
+        let outputs: Vec<String> = (0..4).map(|_| rng.next_u64().to_string()).collect();
+        writeln!(out, "rng {}: {}", check_block_id, outputs.join(" ")).unwrap();
+    }
+
+    let num_aux_blocks = RandomPrecode::with_code_parameters(&parameters(vectors))
+        .num_aux_blocks(vectors.num_blocks);
+    let mut degree_distribution = DegreeDistribution::new(vectors.epsilon);
+    if vectors.adapt_to_small_objects {
+        degree_distribution = degree_distribution.adapted_to(vectors.num_blocks + num_aux_blocks);
+    }
+    let degree_distribution = degree_distribution.with_sampler(vectors.degree_sampler);
+    writeln!(
+        out,
+        "num_aux_blocks={} max_degree={}",
+        num_aux_blocks,
+        degree_distribution.max_degree()
+    )
+    .unwrap();
+
+    let aux_block_members = get_aux_block_members(
+        vectors.rng_algorithm,
+        vectors.stream_id,
+        vectors.num_blocks,
+        num_aux_blocks,
+        vectors.q,
+    );
+    for (aux_index, members) in aux_block_members.iter().enumerate() {
+        if members.is_empty() {
+            continue;
+        }
+        let adjacent: Vec<String> = members.iter().map(|i| i.to_string()).collect();
+        writeln!(
+            out,
+            "aux {}: {}",
+            aux_index + vectors.num_blocks,
+            adjacent.join(" ")
+        )
+        .unwrap();
+    }
+
+    let coder = OnlineCoder::with_code_parameters(vectors.block_size, &parameters(vectors));
+    for (check_block_id, check_block) in coder.encode(data(vectors), vectors.stream_id) {
+        let mut adjacent = get_adjacent_blocks_with_rng(
+            vectors.rng_algorithm,
+            check_block_id,
+            vectors.stream_id,
+            &degree_distribution,
+            vectors.num_blocks + num_aux_blocks,
+        );
+        adjacent.sort_unstable();
+        let adjacent: Vec<String> = adjacent.iter().map(|i| i.to_string()).collect();
+        writeln!(
+            out,
+            "check {}: {} | {}",
+            check_block_id,
+            adjacent.join(" "),
+            to_hex(&check_block)
+        )
+        .unwrap();
+        if check_block_id + 1 == vectors.num_check_blocks {
+            break;
+        }
+    }
+    out
+}
+
+fn golden_path(vectors: &Vectors) -> String {
+    format!(
+        "{}/tests/golden/{}.txt",
+        env!("CARGO_MANIFEST_DIR"),
+        vectors.name
+    )
+}
+
+#[test]
+fn test_golden_vectors() {
+    for vectors in VECTORS {
+        let expected = fs::read_to_string(golden_path(vectors)).unwrap();
+        let actual = render(vectors);
+        for (line, (expected, actual)) in expected.lines().zip(actual.lines()).enumerate() {
+            assert_eq!(expected, actual, "{} line {}", vectors.name, line + 1);
+        }
+        assert_eq!(expected.lines().count(), actual.lines().count());
+    }
+}
+
+#[test]
+fn test_golden_check_blocks_decode() {
+    // Decode using only the recorded check blocks, as a receiver of an old sender would.
+    let vectors = &VECTORS[1];
+    let mut decoder = Decoder::with_code_parameters(
+        vectors.num_blocks,
+        vectors.block_size,
+        vectors.stream_id,
+        &parameters(vectors),
+        0,
+    );
+    let golden = fs::read_to_string(golden_path(vectors)).unwrap();
+    let mut decoded = None;
+    for line in golden.lines().filter(|line| line.starts_with("check ")) {
+        let (id, rest) = line["check ".len()..].split_once(':').unwrap();
+        let (_, hex) = rest.split_once('|').unwrap();
+        decoded = decoder
+            .decode_block(id.parse().unwrap(), &from_hex(hex.trim()))
+            .complete();
+        if decoded.is_some() {
+            break;
+        }
+    }
+    assert_eq!(decoded, Some(data(vectors)));
+}
+
+#[test]
+fn test_chacha20_block_function() {
+    // RFC 8439, section 2.3.2.
+    let key: Vec<u32> = (0..8u32)
+        .map(|i| {
+            u32::from_le_bytes([
+                4 * i as u8,
+                4 * i as u8 + 1,
+                4 * i as u8 + 2,
+                4 * i as u8 + 3,
+            ])
+        })
+        .collect();
+    let mut key_words = [0; 8];
+    key_words.copy_from_slice(&key);
+    let block = chacha_block(&key_words, 1, &[0x0900_0000, 0x4a00_0000, 0], 20);
+    assert_eq!(
+        block,
+        [
+            0xe4e7_f110,
+            0x1559_3bd1,
+            0x1fdd_0f50,
+            0xc471_20a3,
+            0xc7f4_d1c7,
+            0x0368_c033,
+            0x9aaa_2204,
+            0x4e6c_d4c3,
+            0x4664_82d2,
+            0x09aa_9f07,
+            0x05d7_c214,
+            0xa202_8bd9,
+            0xd19c_12b5,
+            0xb94e_16de,
+            0xe883_d0cb,
+            0x4e3c_50a2,
+        ]
+    );
+}
+
+#[test]
+fn test_code_parameters_round_trip() {
+    for vectors in VECTORS {
+        let parameters = parameters(vectors);
+        assert_eq!(
+            CodeParameters::from_bytes(&parameters.to_bytes()),
+            Some(parameters)
+        );
+    }
+    let mut bytes = CodeParameters::default().to_bytes();
+    bytes[1] = 0xff;
+    assert_eq!(CodeParameters::from_bytes(&bytes), None);
+}
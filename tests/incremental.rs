@@ -0,0 +1,142 @@
+extern crate online_codes;
+
+use online_codes::decode::{BlockOutcome, BufferedCheckBlock, Decoder, DecoderCapacity, Progress};
+use online_codes::encode::OnlineCoder;
+
+const NUM_BLOCKS: usize = 1000;
+const BLOCK_SIZE: usize = 8;
+
+fn test_data() -> Vec<u8> {
+    (0..NUM_BLOCKS * BLOCK_SIZE)
+        .map(|i| (i * 5 + 1) as u8)
+        .collect()
+}
+
+fn received_blocks() -> Vec<(u64, Vec<u8>)> {
+    OnlineCoder::new(BLOCK_SIZE)
+        .encode(test_data(), 2)
+        .filter(|(check_block_id, _)| check_block_id % 3 != 2)
+        .take(10 * NUM_BLOCKS)
+        .collect()
+}
+
+fn num_decoded(decoder: &Decoder) -> usize {
+    decoder
+        .blocks_decoded
+        .iter()
+        .filter(|&&decoded| decoded)
+        .count()
+}
+
+fn buffered(decoder: &Decoder) -> Vec<BufferedCheckBlock> {
+    let mut buffered: Vec<_> = decoder
+        .check_block_slots
+        .iter()
+        .flatten()
+        .copied()
+        .collect();
+    buffered.sort_by_key(|buffered| buffered.key);
+    buffered
+}
+
+#[test]
+fn test_incremental_decoding_matches_eager_decoding() {
+    for &budget in &[1, BLOCK_SIZE, 10 * BLOCK_SIZE, usize::MAX] {
+        let mut eager = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 2, 0);
+        let mut incremental = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 2, 0);
+        incremental.incremental = true;
+        let mut decoded = None;
+        for (check_block_id, check_block) in received_blocks() {
+            let eager_decoded = eager.decode_block(check_block_id, &check_block).complete();
+            assert_eq!(
+                incremental.decode_block(check_block_id, &check_block),
+                BlockOutcome::Queued
+            );
+            assert!(incremental.has_pending_work());
+            loop {
+                match incremental.make_progress(budget) {
+                    Progress::Complete(data) => decoded = Some(data),
+                    Progress::Pending => continue,
+                    Progress::Idle => {}
+                }
+                break;
+            }
+            assert!(!incremental.has_pending_work());
+            assert_eq!(decoded, eager_decoded);
+            assert_eq!(incremental.blocks_decoded, eager.blocks_decoded);
+            // Queued blocks take a slot, so buffered ones may end up in different slots.
+            assert_eq!(buffered(&incremental), buffered(&eager));
+            if decoded.is_some() {
+                break;
+            }
+        }
+        assert_eq!(decoded, Some(test_data()));
+    }
+}
+
+#[test]
+fn test_make_progress_is_bounded() {
+    let mut decoder = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 2, 0);
+    decoder.incremental = true;
+    for (check_block_id, check_block) in received_blocks() {
+        decoder.decode_block(check_block_id, &check_block);
+    }
+    assert!(decoder.has_pending_work());
+    let mut num_calls = 0;
+    loop {
+        let before = num_decoded(&decoder);
+        // One step at a time, each of which decodes at most one block.
+        match decoder.make_progress(1) {
+            Progress::Complete(data) => {
+                assert_eq!(data, test_data());
+                break;
+            }
+            Progress::Pending => assert!(num_decoded(&decoder) <= before + 1),
+            Progress::Idle => panic!("ran out of work before decoding"),
+        }
+        num_calls += 1;
+    }
+    assert!(num_calls > NUM_BLOCKS);
+    assert!(!decoder.has_pending_work());
+    assert!(matches!(decoder.make_progress(usize::MAX), Progress::Idle));
+}
+
+#[test]
+fn test_leaving_incremental_mode_decodes_queued_blocks() {
+    let mut decoder = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 2, 0);
+    decoder.incremental = true;
+    let mut blocks = received_blocks().into_iter();
+    for (check_block_id, check_block) in blocks.by_ref().take(NUM_BLOCKS / 2) {
+        decoder.decode_block(check_block_id, &check_block);
+    }
+    assert_eq!(decoder.pending_check_blocks.len(), NUM_BLOCKS / 2);
+    assert!(decoder.buffered_bytes() >= NUM_BLOCKS / 2 * BLOCK_SIZE);
+    decoder.incremental = false;
+    let decoded = blocks.find_map(|(check_block_id, check_block)| {
+        decoder
+            .decode_block(check_block_id, &check_block)
+            .complete()
+    });
+    assert_eq!(decoded, Some(test_data()));
+}
+
+#[test]
+fn test_incremental_decoding_within_capacity() {
+    let capacity = DecoderCapacity {
+        max_buffered_check_blocks: 50,
+        max_buffered_adjacencies: 10_000,
+    };
+    let mut decoder = Decoder::with_capacity(NUM_BLOCKS, BLOCK_SIZE, 2, 0.01, 3, 0, capacity);
+    decoder.incremental = true;
+    for (check_block_id, check_block) in received_blocks().into_iter().take(100) {
+        decoder.decode_block(check_block_id, &check_block);
+    }
+    assert_eq!(decoder.pending_check_blocks.len(), 50);
+    assert_eq!(decoder.num_dropped_check_blocks, 50);
+    while let Progress::Pending = decoder.make_progress(BLOCK_SIZE) {
+        assert!(
+            decoder.num_buffered_check_blocks + decoder.pending_check_blocks.len()
+                <= capacity.max_buffered_check_blocks
+        );
+    }
+}
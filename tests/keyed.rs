@@ -0,0 +1,96 @@
+extern crate online_codes;
+
+use online_codes::adjacency::{get_adjacent_blocks_with_rng, DegreeDistribution};
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+use online_codes::rng::{RngAlgorithm, SecretKey};
+use online_codes::types::CodeParameters;
+
+const NUM_BLOCKS: usize = 400;
+const BLOCK_SIZE: usize = 10;
+
+fn test_data() -> Vec<u8> {
+    (0..NUM_BLOCKS * BLOCK_SIZE)
+        .map(|i| (i * 3 + 11) as u8)
+        .collect()
+}
+
+fn keyed(key: u8) -> CodeParameters {
+    CodeParameters {
+        rng_algorithm: RngAlgorithm::KeyedChaCha8(SecretKey::new([key; 32])),
+        ..CodeParameters::default()
+    }
+}
+
+fn decode(sender: &CodeParameters, receiver: &CodeParameters) -> Option<Vec<u8>> {
+    let coder = OnlineCoder::with_code_parameters(BLOCK_SIZE, sender);
+    let mut decoder = Decoder::with_code_parameters(NUM_BLOCKS, BLOCK_SIZE, 1, receiver, 0);
+    coder
+        .encode(test_data(), 1)
+        .take(5000)
+        .find_map(|(check_block_id, check_block)| {
+            decoder
+                .decode_block(check_block_id, &check_block)
+                .complete()
+        })
+}
+
+#[test]
+fn test_keyed_round_trip() {
+    assert_eq!(decode(&keyed(7), &keyed(7)), Some(test_data()));
+}
+
+#[test]
+fn test_wrong_key_does_not_decode() {
+    assert_ne!(decode(&keyed(7), &keyed(8)), Some(test_data()));
+    assert_ne!(
+        decode(&keyed(7), &CodeParameters::default()),
+        Some(test_data())
+    );
+}
+
+#[test]
+fn test_adjacency_depends_on_key() {
+    let degree_distribution = DegreeDistribution::new(0.01);
+    let adjacency = |rng_algorithm| -> Vec<Vec<usize>> {
+        (0..20)
+            .map(|check_block_id| {
+                let mut blocks = get_adjacent_blocks_with_rng(
+                    rng_algorithm,
+                    check_block_id,
+                    1,
+                    &degree_distribution,
+                    100_000,
+                );
+                blocks.sort_unstable();
+                blocks
+            })
+            .collect()
+    };
+    let with_key = adjacency(keyed(7).rng_algorithm);
+    assert_eq!(with_key, adjacency(keyed(7).rng_algorithm));
+    assert_ne!(with_key, adjacency(keyed(8).rng_algorithm));
+    assert_ne!(with_key, adjacency(RngAlgorithm::ChaCha8));
+}
+
+#[test]
+fn test_key_is_never_sent_or_printed() {
+    let parameters = keyed(0xab);
+    let bytes = parameters.to_bytes();
+    assert!(!bytes.contains(&0xab));
+    assert_eq!(CodeParameters::from_bytes(&bytes), None);
+    assert_eq!(
+        CodeParameters::from_bytes_with_key(&bytes, SecretKey::new([0xab; 32])),
+        Some(parameters)
+    );
+    assert_eq!(
+        CodeParameters::from_bytes_with_key(
+            &CodeParameters::default().to_bytes(),
+            SecretKey::new([0; 32])
+        ),
+        None
+    );
+    let printed = format!("{:?} {:?}", parameters, parameters.rng_algorithm.rng(1, 2));
+    assert!(!printed.contains("171"), "{}", printed);
+    assert!(parameters.rng_algorithm.is_keyed());
+}
@@ -0,0 +1,80 @@
+extern crate online_codes;
+
+use online_codes::{
+    decode_block, decode_stream_block, new_decoder, new_encoder, new_object_decoder,
+    new_object_encoder, next_block,
+};
+
+fn test_data() -> Vec<u8> {
+    (0..1000u32).map(|i| (i * 7 + 3) as u8).collect()
+}
+
+#[test]
+fn test_decode_from_several_streams() {
+    let data = test_data();
+    let object_id = 42;
+    let mut encoders: Vec<_> = (0..3)
+        .map(|stream_id| new_object_encoder(data.clone(), 10, object_id, 1000 + stream_id))
+        .collect();
+    let mut decoder = new_object_decoder(data.len(), 10, object_id);
+
+    let mut decoded = None;
+    'outer: for _ in 0..1000 {
+        for (stream_id, encoder) in encoders.iter_mut().enumerate() {
+            let block = next_block(encoder).unwrap();
+            if let Some(result) =
+                decode_stream_block(1000 + stream_id as u64, block, &mut decoder).complete()
+            {
+                decoded = Some(result);
+                break 'outer;
+            }
+        }
+    }
+    assert_eq!(decoded, Some(data));
+}
+
+#[test]
+fn test_streams_do_not_repeat_blocks() {
+    let data = test_data();
+    let mut first = new_object_encoder(data.clone(), 10, 42, 1);
+    let mut second = new_object_encoder(data, 10, 42, 2);
+    let first_blocks: Vec<_> = (0..20).map(|_| next_block(&mut first).unwrap()).collect();
+    let second_blocks: Vec<_> = (0..20).map(|_| next_block(&mut second).unwrap()).collect();
+    // Not even under another check block id.
+    for (_, block) in &first_blocks {
+        assert!(second_blocks.iter().all(|(_, other)| other != block));
+    }
+}
+
+#[test]
+fn test_single_stream_matches_object() {
+    // A plain encoder/decoder uses its stream id as the object id.
+    let data = test_data();
+    let mut encoder = new_encoder(data.clone(), 10, 5);
+    let mut object_encoder = new_object_encoder(data.clone(), 10, 5, 5);
+    let mut decoder = new_decoder(data.len(), 10, 5);
+    for _ in 0..1000 {
+        let block = next_block(&mut encoder).unwrap();
+        assert_eq!(Some(&block), next_block(&mut object_encoder).as_ref());
+        if let Some(result) = decode_block(block, &mut decoder).complete() {
+            assert_eq!(result, data);
+            return;
+        }
+    }
+    panic!("decoding did not finish");
+}
+
+#[test]
+fn test_adjacent_streams_do_not_overlap() {
+    // Block n + 1 of stream s must not be block n of stream s + 1.
+    let data = test_data();
+    let mut first = new_object_encoder(data.clone(), 10, 42, 1);
+    let mut second = new_object_encoder(data, 10, 42, 2);
+    next_block(&mut first);
+    for _ in 0..20 {
+        assert_ne!(
+            next_block(&mut first).unwrap().1,
+            next_block(&mut second).unwrap().1
+        );
+    }
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "std")]
+
+extern crate online_codes;
+
+use online_codes::sim::{ErasureChannel, Simulation};
+
+// Before aux blocks took part in decoding as equations, these settings needed about 0.74 extra
+// blocks on average.
+#[test]
+fn test_aux_constraints_reduce_overhead() {
+    let report = Simulation {
+        epsilon: 0.1,
+        trials: 20,
+        max_overhead: 2.0,
+        ..Simulation::new(1000, 4, ErasureChannel::Bernoulli { loss: 0.0 })
+    }
+    .run();
+    assert_eq!(report.failure_probability(2.0), 0.0);
+    assert!(report.mean_overhead().unwrap() < 0.4);
+}
+
+#[test]
+fn test_large_message_overhead() {
+    // The outer code should let the overhead approach epsilon as the message grows.
+    let report = Simulation {
+        trials: 5,
+        max_overhead: 1.0,
+        ..Simulation::new(10_000, 1, ErasureChannel::Bernoulli { loss: 0.0 })
+    }
+    .run();
+    assert_eq!(report.failure_probability(1.0), 0.0);
+    assert!(report.mean_overhead().unwrap() < 0.1);
+}
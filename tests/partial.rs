@@ -0,0 +1,65 @@
+#![cfg(feature = "std")]
+
+extern crate online_codes;
+
+use online_codes::{decode_block, new_decoder, new_encoder, next_block};
+use std::env;
+use std::fs;
+
+fn test_data() -> Vec<u8> {
+    // 1003 bytes in blocks of 10, so the last block is padded.
+    (0..1003u32).map(|i| (i * 3 + 1) as u8).collect()
+}
+
+fn partially_decoded(num_check_blocks: usize) -> online_codes::decode::Decoder {
+    let data = test_data();
+    let mut encoder = new_encoder(data.clone(), 10, 4);
+    let mut decoder = new_decoder(data.len(), 10, 4);
+    for _ in 0..num_check_blocks {
+        assert!(!decode_block(next_block(&mut encoder).unwrap(), &mut decoder).is_complete());
+    }
+    decoder
+}
+
+#[test]
+fn test_recovered_ranges_match_blocks() {
+    let data = test_data();
+    let decoder = partially_decoded(80);
+    assert_eq!(decoder.original_len(), data.len());
+    let ranges = decoder.recovered_ranges();
+    assert!(!ranges.is_empty());
+
+    let (blocks_decoded, decoded) = decoder.get_incomplete_result();
+    let mut covered = vec![false; data.len()];
+    for range in &ranges {
+        assert!(range.start < range.end && range.end <= data.len());
+        assert_eq!(&decoded[range.clone()], &data[range.clone()]);
+        for byte in range.clone() {
+            covered[byte] = true;
+        }
+    }
+    for (byte, covered) in covered.into_iter().enumerate() {
+        assert_eq!(covered, blocks_decoded[byte / 10]);
+    }
+    // Ranges are sorted and merged.
+    for pair in ranges.windows(2) {
+        assert!(pair[0].end < pair[1].start);
+    }
+}
+
+#[test]
+fn test_write_sparse_file() {
+    let data = test_data();
+    let decoder = partially_decoded(80);
+    let path = env::temp_dir().join(format!("online_codes_partial_{}", std::process::id()));
+    let ranges = decoder.write_sparse_file(&path).unwrap();
+    let written = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(written.len(), data.len());
+    let mut expected = vec![0; data.len()];
+    for range in ranges {
+        expected[range.clone()].copy_from_slice(&data[range]);
+    }
+    assert_eq!(written, expected);
+}
@@ -0,0 +1,126 @@
+#![cfg(feature = "std")]
+
+extern crate online_codes;
+
+use online_codes::decode::{DecodePolicy, DecodeResult, Decoder, GiveUpReason};
+use online_codes::encode::OnlineCoder;
+use std::time::{Duration, Instant};
+
+const NUM_BLOCKS: usize = 1000;
+const BLOCK_SIZE: usize = 8;
+
+fn test_data() -> Vec<u8> {
+    (0..NUM_BLOCKS * BLOCK_SIZE)
+        .map(|i| (i * 5 + 3) as u8)
+        .collect()
+}
+
+fn check_blocks() -> Vec<(u64, Vec<u8>)> {
+    OnlineCoder::new(BLOCK_SIZE)
+        .encode(test_data(), 0)
+        .take(5000)
+        .collect()
+}
+
+fn decode_with(policy: DecodePolicy, check_blocks: &[(u64, Vec<u8>)]) -> DecodeResult {
+    let mut decoder = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 0, 0);
+    decoder.policy = policy;
+    decoder.into_iter(
+        check_blocks
+            .iter()
+            .map(|(check_block_id, check_block)| (*check_block_id, &check_block[..])),
+    )
+}
+
+#[test]
+fn test_max_received_blocks() {
+    let check_blocks = check_blocks();
+    let policy = DecodePolicy {
+        max_received_blocks: Some(700),
+        ..DecodePolicy::default()
+    };
+    match decode_with(policy, &check_blocks) {
+        DecodeResult::Incomplete(incomplete) => {
+            assert_eq!(incomplete.reason, GiveUpReason::MaxReceivedBlocks);
+            assert_eq!(incomplete.data.len(), NUM_BLOCKS * BLOCK_SIZE);
+            let data = test_data();
+            let mut recovered = 0;
+            for range in &incomplete.recovered_ranges {
+                assert_eq!(incomplete.data[range.clone()], data[range.clone()]);
+                recovered += range.len();
+            }
+            assert!(recovered > 0 && recovered < data.len());
+        }
+        result => panic!("unexpected result {:?}", result.complete()),
+    }
+
+    // A budget which is large enough makes no difference.
+    let policy = DecodePolicy {
+        max_received_blocks: Some(5000),
+        ..DecodePolicy::default()
+    };
+    assert_eq!(
+        decode_with(policy, &check_blocks).complete(),
+        Some(test_data())
+    );
+}
+
+#[test]
+fn test_deadline() {
+    let policy = DecodePolicy {
+        deadline: Some(Instant::now() - Duration::from_secs(1)),
+        ..DecodePolicy::default()
+    };
+    match decode_with(policy, &check_blocks()) {
+        DecodeResult::Incomplete(incomplete) => {
+            assert_eq!(incomplete.reason, GiveUpReason::Deadline)
+        }
+        result => panic!("unexpected result {:?}", result.complete()),
+    }
+}
+
+#[test]
+fn test_max_buffered_bytes() {
+    let policy = DecodePolicy {
+        max_buffered_bytes: Some(4096),
+        ..DecodePolicy::default()
+    };
+    match decode_with(policy, &check_blocks()) {
+        DecodeResult::Incomplete(incomplete) => {
+            assert_eq!(incomplete.reason, GiveUpReason::MaxBufferedBytes)
+        }
+        result => panic!("unexpected result {:?}", result.complete()),
+    }
+}
+
+#[test]
+fn test_empty_object_past_its_deadline() {
+    let mut decoder = Decoder::new(0, BLOCK_SIZE, 0, 0);
+    decoder.policy.deadline = Some(Instant::now() - Duration::from_secs(1));
+    assert_eq!(decoder.check_policy(), None);
+    let incomplete = decoder.clone().give_up(GiveUpReason::Deadline);
+    assert!(incomplete.data.is_empty() && incomplete.recovered_ranges.is_empty());
+    match decoder.into_iter(
+        check_blocks()
+            .iter()
+            .map(|(check_block_id, check_block)| (*check_block_id, &check_block[..])),
+    ) {
+        DecodeResult::Complete(data) => assert!(data.is_empty()),
+        result => panic!("unexpected result {:?}", result.complete()),
+    }
+}
+
+#[test]
+fn test_check_policy_while_feeding_blocks() {
+    let mut decoder = Decoder::new(NUM_BLOCKS, BLOCK_SIZE, 0, 0);
+    decoder.policy.max_received_blocks = Some(3);
+    for (check_block_id, check_block) in check_blocks().into_iter().take(3) {
+        assert_eq!(decoder.check_policy(), None);
+        decoder.decode_block(check_block_id, &check_block);
+    }
+    assert_eq!(decoder.num_received_blocks, 3);
+    assert_eq!(
+        decoder.check_policy(),
+        Some(GiveUpReason::MaxReceivedBlocks)
+    );
+}
@@ -0,0 +1,83 @@
+extern crate online_codes;
+
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+use online_codes::precode::{LdpcPrecode, NoPrecode, Precode, RandomPrecode};
+
+const NUM_BLOCKS: usize = 500;
+const BLOCK_SIZE: usize = 8;
+
+fn test_data() -> Vec<u8> {
+    (0..NUM_BLOCKS * BLOCK_SIZE)
+        .map(|i| (i * 11 + 2) as u8)
+        .collect()
+}
+
+fn round_trip<P: Precode + Clone>(precode: P) {
+    let coder = OnlineCoder::with_precode(BLOCK_SIZE, 0.01, precode.clone());
+    let mut decoder = Decoder::with_precode(NUM_BLOCKS, BLOCK_SIZE, 4, 0.01, &precode, 0);
+    let mut decoded = None;
+    for (check_block_id, check_block) in coder.encode(test_data(), 4).take(10_000) {
+        decoded = decoder
+            .decode_block(check_block_id, &check_block)
+            .complete();
+        if decoded.is_some() {
+            break;
+        }
+    }
+    assert_eq!(decoded, Some(test_data()));
+}
+
+#[test]
+fn test_round_trips() {
+    round_trip(NoPrecode);
+    round_trip(RandomPrecode::new(0.01, 3));
+    round_trip(RandomPrecode::new(0.1, 5));
+    round_trip(LdpcPrecode);
+}
+
+#[test]
+fn test_q_zero_is_plain_lt() {
+    let coder = OnlineCoder::with_parameters(BLOCK_SIZE, 0.01, 0);
+    assert!(coder.outer_encode(&test_data(), 4).is_empty());
+    let mut decoder = Decoder::with_parameters(NUM_BLOCKS, BLOCK_SIZE, 4, 0.01, 0, 0);
+    assert_eq!(decoder.num_augmented_blocks, NUM_BLOCKS);
+    let decoded =
+        coder
+            .encode(test_data(), 4)
+            .take(10_000)
+            .find_map(|(check_block_id, check_block)| {
+                decoder
+                    .decode_block(check_block_id, &check_block)
+                    .complete()
+            });
+    assert_eq!(decoded, Some(test_data()));
+}
+
+#[test]
+fn test_random_precode_matches_default_coder() {
+    let default = OnlineCoder::new(BLOCK_SIZE).outer_encode(&test_data(), 9);
+    let explicit = OnlineCoder::with_precode(BLOCK_SIZE, 0.01, RandomPrecode::default())
+        .outer_encode(&test_data(), 9);
+    assert_eq!(default, explicit);
+}
+
+#[test]
+fn test_ldpc_structure() {
+    for &num_blocks in &[1, 2, 10, 1000, 12345] {
+        let num_aux_blocks = LdpcPrecode.num_aux_blocks(num_blocks);
+        assert!(num_aux_blocks >= 3);
+        assert!((2..num_aux_blocks).all(|d| !num_aux_blocks.is_multiple_of(d)));
+        let members = LdpcPrecode.aux_block_members(0, num_blocks);
+        assert_eq!(members.len(), num_aux_blocks);
+        let mut counts = vec![0; num_blocks];
+        for aux_members in &members {
+            assert!(aux_members.windows(2).all(|pair| pair[0] < pair[1]));
+            for &block_index in aux_members {
+                counts[block_index] += 1;
+            }
+        }
+        assert!(counts.iter().all(|&count| count == 3));
+    }
+    assert_eq!(LdpcPrecode.num_aux_blocks(0), 0);
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "std")]
+
+extern crate online_codes;
+
+use online_codes::redundancy::{blocks_to_send, probability_received, RedundancyCalculator};
+use online_codes::sim::{ErasureChannel, Simulation};
+use std::sync::Arc;
+
+#[test]
+fn test_probability_received() {
+    assert_eq!(probability_received(10, 10, 0.0), 1.0);
+    assert_eq!(probability_received(10, 11, 0.0), 0.0);
+    assert!((probability_received(2, 1, 0.5) - 0.75).abs() < 1e-12);
+    assert!((probability_received(3, 3, 0.1) - 0.729).abs() < 1e-12);
+    // Large enough that the naive terms underflow.
+    let p = probability_received(100_000, 50_000, 0.5);
+    assert!((p - 0.5).abs() < 0.01);
+}
+
+#[test]
+fn test_blocks_to_send() {
+    assert_eq!(blocks_to_send(&[Some(100)], 0.0, 0.99), Some(100));
+    assert_eq!(blocks_to_send(&[Some(100)], 0.0, 1.0), Some(100));
+    assert_eq!(blocks_to_send(&[Some(100)], 0.1, 1.0), None);
+    assert_eq!(blocks_to_send(&[Some(100)], 1.0, 0.5), None);
+    assert_eq!(blocks_to_send(&[Some(100), None], 0.0, 0.9), None);
+    assert_eq!(blocks_to_send(&[Some(100), Some(200)], 0.0, 0.5), Some(100));
+    assert_eq!(blocks_to_send(&[Some(100), Some(200)], 0.0, 0.6), Some(200));
+
+    let sent = blocks_to_send(&[Some(1000)], 0.2, 0.99).unwrap();
+    assert!(probability_received(sent, 1000, 0.2) >= 0.99);
+    assert!(probability_received(sent - 1, 1000, 0.2) < 0.99);
+    assert!(sent > 1250 && sent < 1350);
+}
+
+#[test]
+fn test_analytic_model() {
+    let calculator = RedundancyCalculator::default();
+    let needed = calculator.analytic_needed_blocks(1000);
+    assert!(needed > 1000 && needed < 1100);
+    assert_eq!(
+        calculator.analytic_blocks_to_send(1000 * 64, 64, 0.0, 0.99),
+        Some(needed)
+    );
+    let mut previous = needed;
+    for &loss in &[0.1, 0.3, 0.5] {
+        let sent = calculator
+            .analytic_blocks_to_send(1000 * 64 - 1, 64, loss, 0.99)
+            .unwrap();
+        assert!(sent as f64 * (1.0 - loss) >= needed as f64);
+        assert!(sent > previous);
+        previous = sent;
+    }
+}
+
+#[test]
+fn test_simulated_model_is_cached() {
+    let calculator = RedundancyCalculator::with_trials(0.01, 3, 20, 0);
+    let first = calculator.simulated_needed_blocks(100);
+    assert_eq!(first.len(), 20);
+    assert!(first.iter().flatten().all(|&needed| needed >= 100));
+    assert!(Arc::ptr_eq(
+        &first,
+        &calculator.simulated_needed_blocks(100)
+    ));
+    assert_eq!(
+        calculator.simulated_blocks_to_send(0, 16, 0.5, 0.9),
+        Some(0)
+    );
+}
+
+#[test]
+fn test_simulated_model_meets_target() {
+    let calculator = RedundancyCalculator::default();
+    let loss = 0.25;
+    let sent = calculator
+        .simulated_blocks_to_send(300 * 16, 16, loss, 0.9)
+        .unwrap();
+
+    // Fresh objects and losses, sending that many blocks each.
+    let report = Simulation {
+        trials: 200,
+        seed: 1_000_000,
+        ..Simulation::new(300, 16, ErasureChannel::Bernoulli { loss })
+    }
+    .run();
+    let successes = report
+        .trials
+        .iter()
+        .filter(|trial| trial.completed && trial.blocks_sent <= sent)
+        .count();
+    assert!(successes >= 170, "{} of 200 decoded", successes);
+}
@@ -0,0 +1,93 @@
+extern crate online_codes;
+
+use online_codes::adjacency::{
+    get_adjacent_blocks, get_adjacent_blocks_with_rng, DegreeDistribution,
+};
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+use online_codes::rng::{AdjacencyRng, RngAlgorithm, XoshiroRng};
+use online_codes::types::CodeParameters;
+
+const ALGORITHMS: [RngAlgorithm; 2] = [RngAlgorithm::Xoshiro256StarStar, RngAlgorithm::ChaCha8];
+
+#[test]
+fn test_round_trip_with_each_algorithm() {
+    let data: Vec<u8> = (0..4000u32).map(|i| (i * 5 + 1) as u8).collect();
+    for &rng_algorithm in &ALGORITHMS {
+        let parameters = CodeParameters {
+            rng_algorithm,
+            ..CodeParameters::default()
+        };
+        let coder = OnlineCoder::with_code_parameters(10, &parameters);
+        let mut decoder = Decoder::with_code_parameters(400, 10, 8, &parameters, 0);
+        let decoded =
+            coder
+                .encode(data.clone(), 8)
+                .take(10_000)
+                .find_map(|(check_block_id, check_block)| {
+                    decoder
+                        .decode_block(check_block_id, &check_block)
+                        .complete()
+                });
+        assert_eq!(decoded, Some(data.clone()), "{:?}", rng_algorithm);
+    }
+}
+
+#[test]
+fn test_algorithm_ids_are_stable() {
+    for &rng_algorithm in &ALGORITHMS {
+        assert_eq!(
+            RngAlgorithm::from_id(rng_algorithm.id()),
+            Some(rng_algorithm)
+        );
+    }
+    assert_eq!(RngAlgorithm::Xoshiro256StarStar.id(), 0);
+    assert_eq!(RngAlgorithm::ChaCha8.id(), 1);
+    assert_eq!(RngAlgorithm::from_id(2), None);
+}
+
+#[test]
+fn test_default_algorithm_matches_xoshiro() {
+    let degree_distribution = DegreeDistribution::new(0.01);
+    for check_block_id in 0..100 {
+        let selected = get_adjacent_blocks_with_rng(
+            RngAlgorithm::default(),
+            check_block_id,
+            3,
+            &degree_distribution,
+            1000,
+        );
+        let xoshiro = get_adjacent_blocks(check_block_id, 3, &degree_distribution, 1000);
+        assert!(selected.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(selected, xoshiro);
+    }
+    let mut selected = RngAlgorithm::default().rng(3, 4);
+    let mut xoshiro = XoshiroRng::new(3, 4);
+    for _ in 0..10 {
+        assert_eq!(selected.next_u64(), xoshiro.next_u64());
+    }
+}
+
+#[test]
+fn test_chacha_streams_are_independent() {
+    // Different streams and check blocks never share a keystream.
+    let first: Vec<u64> = (0..4)
+        .map(|check_block_id| RngAlgorithm::ChaCha8.rng(1, check_block_id).next_u64())
+        .chain((0..4).map(|check_block_id| RngAlgorithm::ChaCha8.rng(2, check_block_id).next_u64()))
+        .collect();
+    for (i, a) in first.iter().enumerate() {
+        assert!(first[i + 1..].iter().all(|b| a != b));
+    }
+}
+
+#[test]
+fn test_xoshiro_streams_are_independent() {
+    // The first output decides the degree, so it must differ between streams too.
+    let first: Vec<u64> = (0..4)
+        .map(|check_block_id| XoshiroRng::new(1, check_block_id).next_u64())
+        .chain((0..4).map(|check_block_id| XoshiroRng::new(2, check_block_id).next_u64()))
+        .collect();
+    for (i, a) in first.iter().enumerate() {
+        assert!(first[i + 1..].iter().all(|b| a != b));
+    }
+}
@@ -0,0 +1,60 @@
+extern crate online_codes;
+
+use hashbrown::HashSet;
+use online_codes::adjacency::{
+    get_aux_block_members, sample_distinct, sample_with_exclusive_repeats,
+    sample_with_exclusive_repeats_into,
+};
+use online_codes::rng::{AdjacencyRng, RngAlgorithm, XoshiroRng};
+
+// The original sampler, whose sets must not change.
+fn hash_set_sample<R: AdjacencyRng>(rng: &mut R, high_exclusive: usize, num: usize) -> Vec<usize> {
+    let mut selected = HashSet::with_capacity(num);
+    for _ in 0..num {
+        let sample = rng.below(high_exclusive);
+        if !selected.insert(sample) {
+            selected.remove(&sample);
+        }
+    }
+    let mut selected: Vec<usize> = selected.into_iter().collect();
+    selected.sort_unstable();
+    selected
+}
+
+#[test]
+fn test_same_sets_in_sorted_order() {
+    for &(high_exclusive, num) in &[(1, 5), (2, 3), (10, 10), (100, 7), (50, 200), (4096, 2000)] {
+        for seed in 0..50 {
+            let expected = hash_set_sample(&mut XoshiroRng::new(seed, 1), high_exclusive, num);
+            let actual =
+                sample_with_exclusive_repeats(&mut XoshiroRng::new(seed, 1), high_exclusive, num);
+            assert_eq!(actual, expected);
+        }
+    }
+}
+
+#[test]
+fn test_buffer_is_reused() {
+    let mut rng = XoshiroRng::new(3, 3);
+    let mut out = vec![99; 10];
+    sample_with_exclusive_repeats_into(&mut rng, 1000, 4, &mut out);
+    assert!(out.len() <= 4 && out.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(out.iter().all(|&i| i < 1000));
+}
+
+#[test]
+fn test_sample_distinct_is_sorted_and_distinct() {
+    for seed in 0..100 {
+        let sample = sample_distinct(&mut XoshiroRng::new(seed, 0), 20, 1 + seed as usize % 25);
+        assert_eq!(sample.len(), usize::min(1 + seed as usize % 25, 20));
+        assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(sample.iter().all(|&i| i < 20));
+    }
+}
+
+#[test]
+fn test_aux_members_are_deterministic() {
+    let first = get_aux_block_members(RngAlgorithm::default(), 1, 1000, 17, 3);
+    let second = get_aux_block_members(RngAlgorithm::default(), 1, 1000, 17, 3);
+    assert_eq!(first, second);
+}
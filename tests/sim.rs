@@ -0,0 +1,97 @@
+#![cfg(feature = "std")]
+
+extern crate online_codes;
+
+use online_codes::sim::{ChannelState, ErasureChannel, Simulation};
+use rand::thread_rng;
+
+#[test]
+fn test_lossless_trials_complete() {
+    let report = Simulation {
+        trials: 20,
+        ..Simulation::new(200, 8, ErasureChannel::Bernoulli { loss: 0.0 })
+    }
+    .run();
+    assert_eq!(report.trials.len(), 20);
+    for trial in &report.trials {
+        assert!(trial.completed);
+        assert_eq!(trial.blocks_sent, trial.blocks_received);
+    }
+    assert_eq!(report.failure_probability(1.0), 0.0);
+    assert!(report.overhead_quantile(1.0).unwrap() <= 1.0);
+}
+
+#[test]
+fn test_trials_are_reproducible() {
+    let simulation = Simulation {
+        trials: 10,
+        seed: 1234,
+        ..Simulation::new(
+            100,
+            8,
+            ErasureChannel::GilbertElliott {
+                good_to_bad: 0.05,
+                bad_to_good: 0.2,
+                good_loss: 0.0,
+                bad_loss: 0.9,
+            },
+        )
+    };
+    let first = simulation.run();
+    let second = simulation.run();
+    for (a, b) in first.trials.iter().zip(&second.trials) {
+        assert_eq!(a.blocks_sent, b.blocks_sent);
+        assert_eq!(a.blocks_received, b.blocks_received);
+        assert_eq!(a.completed, b.completed);
+    }
+}
+
+#[test]
+fn test_pattern_channel() {
+    let mut channel = ChannelState::new(ErasureChannel::Pattern(vec![true, false, false]));
+    let erased: Vec<bool> = (0..6)
+        .map(|_| channel.is_erased(&mut thread_rng()))
+        .collect();
+    assert_eq!(erased, vec![true, false, false, true, false, false]);
+
+    let report = Simulation {
+        trials: 5,
+        ..Simulation::new(100, 8, ErasureChannel::Pattern(vec![true, false, false]))
+    }
+    .run();
+    for trial in &report.trials {
+        assert!(trial.completed);
+        // Every third block sent is erased, starting with the first.
+        let erased = trial.blocks_sent.div_ceil(3);
+        assert_eq!(trial.blocks_received, trial.blocks_sent - erased);
+        // The last block sent completed decoding, so it can't have been erased.
+        assert_ne!(trial.blocks_sent % 3, 1);
+    }
+}
+
+#[test]
+fn test_channel_which_erases_everything() {
+    let report = Simulation {
+        trials: 2,
+        max_sent_per_received: 10,
+        ..Simulation::new(100, 8, ErasureChannel::Pattern(vec![true]))
+    }
+    .run();
+    for trial in &report.trials {
+        assert!(!trial.completed);
+        assert_eq!(trial.blocks_received, 0);
+        assert_eq!(trial.blocks_sent, 2000);
+    }
+    assert_eq!(report.failure_probability(1.0), 1.0);
+}
+
+#[test]
+fn test_failure_probability_decreases_with_overhead() {
+    let report = Simulation {
+        trials: 20,
+        ..Simulation::new(200, 8, ErasureChannel::Bernoulli { loss: 0.3 })
+    }
+    .run();
+    assert!(report.failure_probability(0.0) >= report.failure_probability(0.2));
+    assert!(report.failure_probability(0.2) >= report.failure_probability(1.0));
+}
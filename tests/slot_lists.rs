@@ -0,0 +1,57 @@
+extern crate online_codes;
+
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+use online_codes::slot_lists::SlotLists;
+
+#[test]
+fn test_slot_lists() {
+    let mut lists = SlotLists::new(3);
+    assert!(lists.is_empty());
+    lists.push(0, 5);
+    lists.push(2, 7);
+    lists.push(0, 6);
+    assert_eq!(lists.len(), 3);
+    assert_eq!(lists.pop(1), None);
+    assert_eq!(lists.pop(0), Some(6));
+    assert_eq!(lists.pop(0), Some(5));
+    assert_eq!(lists.pop(0), None);
+    assert_eq!(lists.len(), 1);
+
+    // Freed entries are reused without disturbing the other lists.
+    lists.push(1, 8);
+    lists.push(1, 9);
+    assert_eq!(lists.pop(1), Some(9));
+    assert_eq!(lists.pop(1), Some(8));
+    assert_eq!(lists.pop(2), Some(7));
+    assert!(lists.is_empty());
+}
+
+#[test]
+fn test_check_block_slots_are_reused() {
+    let block_size = 4;
+    let num_blocks = 20_000;
+    let data: Vec<u8> = (0..num_blocks * block_size)
+        .map(|i| (i * 7 + 3) as u8)
+        .collect();
+    let mut decoder = Decoder::new(num_blocks, block_size, 1, 0);
+    let mut max_buffered = 0;
+    let mut decoded = None;
+    // Drop every third block so that plenty of check blocks wait in slots.
+    for (check_block_id, check_block) in OnlineCoder::new(block_size)
+        .encode(data.clone(), 1)
+        .filter(|(check_block_id, _)| check_block_id % 3 != 0)
+        .take(3 * num_blocks)
+    {
+        decoded = decoder
+            .decode_block(check_block_id, &check_block)
+            .complete();
+        max_buffered = usize::max(max_buffered, decoder.num_buffered_check_blocks);
+        assert!(decoder.decode_stack.is_empty());
+        if decoded.is_some() {
+            break;
+        }
+    }
+    assert_eq!(decoded, Some(data));
+    assert_eq!(decoder.check_block_slots.len(), max_buffered);
+}
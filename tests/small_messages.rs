@@ -0,0 +1,106 @@
+#![cfg(feature = "std")]
+
+extern crate online_codes;
+
+use online_codes::adjacency::{get_adjacent_blocks, num_aux_blocks, DegreeDistribution};
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+use online_codes::precode::{Precode, RandomPrecode};
+use online_codes::sim::{ErasureChannel, Simulation};
+use online_codes::types::CodeParameters;
+
+fn adapted() -> CodeParameters {
+    CodeParameters {
+        adapt_to_small_objects: true,
+        ..CodeParameters::default()
+    }
+}
+
+#[test]
+fn test_large_objects_keep_the_paper_distribution() {
+    let paper = DegreeDistribution::new(0.01);
+    assert_eq!(DegreeDistribution::for_blocks(0.01, 4096), paper);
+    assert_eq!(
+        DegreeDistribution::for_blocks(0.01, paper.max_degree()),
+        paper
+    );
+    assert!(!paper.is_truncated());
+}
+
+#[test]
+fn test_degrees_fit_small_objects() {
+    for &num_blocks in &[1, 2, 4, 10, 100] {
+        let degree_distribution = DegreeDistribution::for_blocks(0.01, num_blocks);
+        assert!(degree_distribution.is_truncated());
+        assert_eq!(degree_distribution.max_degree(), num_blocks);
+        for check_block_id in 0..1000 {
+            let mut adjacent =
+                get_adjacent_blocks(check_block_id, 3, &degree_distribution, num_blocks);
+            // Never empty, and no block repeated or toggled out again.
+            assert!(!adjacent.is_empty());
+            let degree = adjacent.len();
+            adjacent.sort_unstable();
+            adjacent.dedup();
+            assert_eq!(adjacent.len(), degree);
+            assert!(adjacent.iter().all(|&block| block < num_blocks));
+        }
+    }
+}
+
+#[test]
+fn test_small_objects_have_no_aux_blocks() {
+    let precode = RandomPrecode::with_code_parameters(&adapted());
+    assert_eq!(precode.num_aux_blocks(4), 0);
+    assert_eq!(precode.num_aux_blocks(100), 0);
+    assert_eq!(precode.num_aux_blocks(4096), 68);
+    // Unless asked to, the outer code stays as it was.
+    assert_eq!(RandomPrecode::default().num_aux_blocks(4), 1);
+    assert_eq!(num_aux_blocks(100, 0.01, 3), 2);
+}
+
+#[test]
+fn test_adaptation_is_opt_in() {
+    let data: Vec<u8> = (0..40u8).collect();
+    let paper: Vec<_> = OnlineCoder::new(4)
+        .encode(data.clone(), 1)
+        .take(20)
+        .collect();
+    let coder = OnlineCoder::with_code_parameters(4, &CodeParameters::default());
+    assert_eq!(
+        coder.encode(data.clone(), 1).take(20).collect::<Vec<_>>(),
+        paper
+    );
+    let coder = OnlineCoder::with_code_parameters(4, &adapted());
+    assert_ne!(
+        coder.encode(data.clone(), 1).take(20).collect::<Vec<_>>(),
+        paper
+    );
+
+    assert_eq!(adapted().to_bytes()[0], 3);
+    assert_eq!(
+        CodeParameters::from_bytes(&adapted().to_bytes()),
+        Some(adapted())
+    );
+    let mut decoder = Decoder::with_code_parameters(10, 4, 1, &adapted(), 0);
+    let decoded = coder
+        .encode(data.clone(), 1)
+        .take(1000)
+        .find_map(|(id, block)| decoder.decode_block(id, &block).complete());
+    assert_eq!(decoded, Some(data));
+}
+
+// With the paper's distribution these needed about 1.4 and 0.5 extra blocks on average.
+#[test]
+fn test_small_message_overhead() {
+    for &(num_blocks, max_mean_overhead) in &[(4, 0.6), (100, 0.35)] {
+        let report = Simulation {
+            trials: 200,
+            max_overhead: 10.0,
+            adapt_to_small_objects: true,
+            ..Simulation::new(num_blocks, 4, ErasureChannel::Bernoulli { loss: 0.0 })
+        }
+        .run();
+        assert_eq!(report.failure_probability(10.0), 0.0);
+        assert!(report.mean_overhead().unwrap() < max_mean_overhead);
+    }
+}
@@ -0,0 +1,145 @@
+extern crate online_codes;
+
+use online_codes::decode::Decoder;
+use online_codes::encode::OnlineCoder;
+#[cfg(feature = "std")]
+use online_codes::storage::MmapStorage;
+use online_codes::storage::{SliceStorage, Storage};
+use online_codes::types::CodeParameters;
+
+const BLOCK_SIZE: usize = 10;
+const NUM_BLOCKS: usize = 101;
+const PAD: usize = 7;
+
+fn test_data() -> Vec<u8> {
+    // The last block is padded.
+    (0..NUM_BLOCKS * BLOCK_SIZE - PAD)
+        .map(|i| (i * 29 + 3) as u8)
+        .collect()
+}
+
+fn encoded_blocks() -> impl Iterator<Item = (u64, Vec<u8>)> {
+    let mut data = test_data();
+    data.resize(NUM_BLOCKS * BLOCK_SIZE, 0);
+    OnlineCoder::new(BLOCK_SIZE).encode(data, 6).take(10_000)
+}
+
+fn decode<S: Storage>(decoder: &mut Decoder<S>) -> Option<S::Output> {
+    for (check_block_id, check_block) in encoded_blocks() {
+        if let Some(output) = decoder
+            .decode_block(check_block_id, &check_block)
+            .complete()
+        {
+            return Some(output);
+        }
+    }
+    None
+}
+
+fn storage_len() -> usize {
+    Decoder::storage_len(NUM_BLOCKS, BLOCK_SIZE, 0.01, 3)
+}
+
+#[test]
+fn test_slice_storage() {
+    let mut buf = vec![0xff; storage_len() + 50 * BLOCK_SIZE];
+    let storage = SliceStorage::new(&mut buf, storage_len());
+    let mut decoder = Decoder::with_storage(NUM_BLOCKS, BLOCK_SIZE, 6, 0.01, 3, PAD, storage);
+    assert_eq!(decode(&mut decoder), Some(()));
+    drop(decoder);
+    assert_eq!(&buf[..test_data().len()], &test_data()[..]);
+}
+
+#[test]
+fn test_full_slice_storage_drops_check_blocks() {
+    // No room for any check blocks, so only those which decode a block immediately are used.
+    let mut buf = vec![0; storage_len()];
+    let storage = SliceStorage::new(&mut buf, storage_len());
+    let mut decoder = Decoder::with_storage(NUM_BLOCKS, BLOCK_SIZE, 6, 0.01, 3, PAD, storage);
+    decode(&mut decoder);
+    assert!(decoder.num_dropped_check_blocks > 0);
+    assert_eq!(decoder.num_buffered_check_blocks, 0);
+}
+
+#[test]
+fn test_memory_storage_matches_slice_storage() {
+    let mut buf = vec![0; storage_len() + 1000 * BLOCK_SIZE];
+    let storage = SliceStorage::new(&mut buf, storage_len());
+    let mut slice_decoder = Decoder::with_storage(NUM_BLOCKS, BLOCK_SIZE, 6, 0.01, 3, PAD, storage);
+    let mut memory_decoder = Decoder::with_parameters(NUM_BLOCKS, BLOCK_SIZE, 6, 0.01, 3, PAD);
+    for (check_block_id, check_block) in encoded_blocks().take(50) {
+        slice_decoder.decode_block(check_block_id, &check_block);
+        memory_decoder.decode_block(check_block_id, &check_block);
+        assert_eq!(
+            slice_decoder.get_incomplete_result(),
+            memory_decoder.get_incomplete_result()
+        );
+    }
+}
+
+#[test]
+fn test_storage_for_code_parameters() {
+    // Adapted to its small size, the object has no aux blocks.
+    let parameters = CodeParameters {
+        adapt_to_small_objects: true,
+        ..CodeParameters::default()
+    };
+    let storage_len = Decoder::storage_len_for(NUM_BLOCKS, BLOCK_SIZE, &parameters);
+    assert_eq!(storage_len, NUM_BLOCKS * BLOCK_SIZE);
+    let mut buf = vec![0; storage_len + 1000 * BLOCK_SIZE];
+    let storage = SliceStorage::new(&mut buf, storage_len);
+    let mut decoder = Decoder::with_code_parameters_and_storage(
+        NUM_BLOCKS,
+        BLOCK_SIZE,
+        6,
+        &parameters,
+        PAD,
+        storage,
+    );
+    let mut data = test_data();
+    data.resize(NUM_BLOCKS * BLOCK_SIZE, 0);
+    let decoded = OnlineCoder::with_code_parameters(BLOCK_SIZE, &parameters)
+        .encode(data, 6)
+        .take(10_000)
+        .find_map(|(check_block_id, check_block)| {
+            decoder
+                .decode_block(check_block_id, &check_block)
+                .complete()
+        });
+    assert_eq!(decoded, Some(()));
+    drop(decoder);
+    assert_eq!(&buf[..test_data().len()], &test_data()[..]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_mmap_storage() {
+    let path = std::env::temp_dir().join(format!("online_codes_mmap_{}", std::process::id()));
+    let storage = MmapStorage::create(&path, storage_len()).unwrap();
+    let mut decoder = Decoder::with_storage(NUM_BLOCKS, BLOCK_SIZE, 6, 0.01, 3, PAD, storage);
+    let result = decode(&mut decoder);
+    drop(decoder);
+    let written = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(result.unwrap().is_ok());
+    assert_eq!(written, test_data());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_mmap_storage_keeps_its_map_when_it_cannot_grow() {
+    let path = std::env::temp_dir().join(format!("online_codes_grow_{}", std::process::id()));
+    let mut storage = MmapStorage::create(&path, 16).unwrap();
+    storage.augmented_data_mut().copy_from_slice(&[7; 16]);
+    storage
+        .check_block_slot(0, 8)
+        .unwrap()
+        .copy_from_slice(&[9; 8]);
+    // Far beyond what any file system allows.
+    assert!(storage.check_block_slot(1 << 58, 8).is_none());
+    assert_eq!(storage.augmented_data(), &[7; 16]);
+    assert_eq!(storage.check_block_slot(0, 8).unwrap(), &[9; 8]);
+    drop(storage);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,91 @@
+extern crate online_codes;
+
+use online_codes::stream::{StreamDecoder, StreamDelivery, StreamEncoder};
+
+fn source_block(index: usize, block_size: usize) -> Vec<u8> {
+    (0..block_size)
+        .map(|i| (index * 31 + i * 7) as u8)
+        .collect()
+}
+
+fn run_stream(
+    num_blocks: usize,
+    window_size: usize,
+    check_blocks_per_push: usize,
+    loss: usize,
+    copies: usize,
+) {
+    let block_size = 16;
+    let mut encoder = StreamEncoder::new(block_size, window_size, 7);
+    let mut decoder = StreamDecoder::new(block_size, window_size, 7);
+    let mut delivered = Vec::new();
+    let mut sent = 0;
+    let mut deliver = |block, delivered: &mut Vec<StreamDelivery>| {
+        sent += 1;
+        // Drop every `loss`th block, and send the rest `copies` times.
+        if loss == 0 || sent % loss != 0 {
+            for _ in 0..copies {
+                delivered.extend(decoder.decode_block(&block));
+            }
+        }
+    };
+
+    for i in 0..num_blocks {
+        encoder.push_block(&source_block(i, block_size));
+        for _ in 0..check_blocks_per_push {
+            deliver(encoder.next_block().unwrap(), &mut delivered);
+        }
+    }
+    // Keep sending over the final window until everything has been delivered.
+    for _ in 0..100 * window_size {
+        if delivered.len() == num_blocks {
+            break;
+        }
+        deliver(encoder.next_block().unwrap(), &mut delivered);
+    }
+
+    assert_eq!(delivered.len(), num_blocks);
+    for (i, delivery) in delivered.into_iter().enumerate() {
+        assert_eq!(
+            delivery,
+            StreamDelivery::Block(i as u64, source_block(i, block_size))
+        );
+    }
+}
+
+#[test]
+fn test_stream_in_order_delivery() {
+    run_stream(200, 32, 3, 0, 1);
+}
+
+#[test]
+fn test_stream_with_loss() {
+    run_stream(200, 32, 4, 5, 1);
+}
+
+#[test]
+fn test_stream_with_duplicates() {
+    run_stream(200, 32, 4, 5, 2);
+}
+
+#[test]
+fn test_stream_with_small_and_large_windows() {
+    run_stream(100, 1, 3, 0, 1);
+    run_stream(3000, 2500, 2, 0, 1);
+}
+
+#[test]
+fn test_stream_reports_lost_blocks() {
+    let block_size = 4;
+    let mut encoder = StreamEncoder::new(block_size, 2, 0);
+    let mut decoder = StreamDecoder::new(block_size, 2, 0);
+    for i in 0..4 {
+        encoder.push_block(&source_block(i, block_size));
+    }
+    // The window has moved on to blocks 2 and 3 without anything being sent for blocks 0 and 1.
+    let delivered = decoder.decode_block(&encoder.next_block().unwrap());
+    assert_eq!(
+        &delivered[..2],
+        &[StreamDelivery::Lost(0), StreamDelivery::Lost(1)]
+    );
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "std")]
+
+extern crate online_codes;
+
+use online_codes::sim::{ChannelState, ErasureChannel};
+use online_codes::udp::{PacketHeader, UdpReceiver, UdpSender, HEADER_LEN};
+use rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256StarStar;
+use std::env;
+use std::fs;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const MAX_LEN: u64 = 1 << 20;
+
+fn test_data() -> Vec<u8> {
+    (0..100_003u32).map(|i| (i * 31 + 7) as u8).collect()
+}
+
+#[test]
+fn test_packet_header_round_trip() {
+    let header = PacketHeader {
+        stream_id: 0x0102_0304_0506_0708,
+        len: 12345,
+        block_size: 512,
+        check_block_id: u64::MAX,
+    };
+    let mut buf = [0; HEADER_LEN];
+    header.write(&mut buf);
+    assert_eq!(PacketHeader::read(&buf), Some(header));
+    assert_eq!(PacketHeader::read(&buf[1..]), None);
+}
+
+// Forwards packets to `target` until `done` is set, dropping those lost on `channel`. Returns its
+// address and the number of packets it has dropped.
+fn lossy_relay(
+    target: SocketAddr,
+    channel: ErasureChannel,
+    done: Arc<AtomicBool>,
+) -> (SocketAddr, Arc<AtomicU64>) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_millis(10)))
+        .unwrap();
+    let address = socket.local_addr().unwrap();
+    let dropped = Arc::new(AtomicU64::new(0));
+    let relay_dropped = dropped.clone();
+    thread::spawn(move || {
+        let mut channel = ChannelState::new(channel);
+        let mut rng = Xoshiro256StarStar::seed_from_u64(5);
+        let mut packet = vec![0; 65536];
+        while !done.load(Ordering::Relaxed) {
+            if let Ok(len) = socket.recv(&mut packet) {
+                if channel.is_erased(&mut rng) {
+                    relay_dropped.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    let _ = socket.send_to(&packet[..len], target);
+                }
+            }
+        }
+    });
+    (address, dropped)
+}
+
+fn send_packet(target: SocketAddr, header: PacketHeader) {
+    let mut packet = vec![0; HEADER_LEN + header.block_size as usize];
+    header.write(&mut packet);
+    UdpSocket::bind("127.0.0.1:0")
+        .unwrap()
+        .send_to(&packet, target)
+        .unwrap();
+}
+
+#[test]
+fn test_loopback_transfer_with_loss() {
+    let mut receiver = UdpReceiver::bind("127.0.0.1:0".parse().unwrap(), 9, MAX_LEN).unwrap();
+    let done = Arc::new(AtomicBool::new(false));
+    let (target, dropped) = lossy_relay(
+        receiver.local_addr().unwrap(),
+        ErasureChannel::Bernoulli { loss: 0.3 },
+        done.clone(),
+    );
+
+    // Packets which aren't check blocks, or belong to another stream, are ignored, however large
+    // a transfer they announce.
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .send_to(b"not a check block", receiver.local_addr().unwrap())
+        .unwrap();
+    for (stream_id, len) in [(8, 1000), (9, u64::MAX / 2)] {
+        send_packet(
+            receiver.local_addr().unwrap(),
+            PacketHeader {
+                stream_id,
+                len,
+                block_size: 16,
+                check_block_id: 0,
+            },
+        );
+    }
+
+    let path = env::temp_dir().join(format!("online_codes_udp_{}", std::process::id()));
+    fs::write(&path, test_data()).unwrap();
+    let mut sender = UdpSender::from_file(&path, 1024, 9, target, 20_000.0).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let sender_done = done.clone();
+    let sender_thread =
+        thread::spawn(move || sender.send_until(|| sender_done.load(Ordering::Relaxed)));
+
+    let out_path = env::temp_dir().join(format!("online_codes_udp_out_{}", std::process::id()));
+    let result = receiver.receive_to_file(&out_path, Some(Duration::from_secs(60)));
+    done.store(true, Ordering::Relaxed);
+    sender_thread.join().unwrap().unwrap();
+    result.unwrap();
+
+    assert_eq!(fs::read(&out_path).unwrap(), test_data());
+    fs::remove_file(&out_path).unwrap();
+    assert!(dropped.load(Ordering::Relaxed) > 0);
+    assert!(receiver.packets_ignored >= 3);
+}
+
+#[test]
+fn test_empty_transfer() {
+    let mut receiver = UdpReceiver::bind("127.0.0.1:0".parse().unwrap(), 4, MAX_LEN).unwrap();
+    let mut sender =
+        UdpSender::new(Vec::new(), 64, 4, receiver.local_addr().unwrap(), 0.0).unwrap();
+    sender.send_block().unwrap();
+    let data = receiver.receive(Some(Duration::from_secs(10))).unwrap();
+    assert!(data.is_empty());
+}
+
+#[test]
+fn test_receive_times_out() {
+    let mut receiver = UdpReceiver::bind("127.0.0.1:0".parse().unwrap(), 0, MAX_LEN).unwrap();
+    let error = receiver
+        .receive(Some(Duration::from_millis(50)))
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+}